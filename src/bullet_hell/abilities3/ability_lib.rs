@@ -3,12 +3,31 @@ use std::{path::Path, time::Duration};
 use bevy::{ecs::system::SystemId, prelude::*};
 use bevy_inspector_egui::prelude::*;
 
-use crate::{upgrades::GlobalUpgrade, utils::resources::SelectionsPool};
+use crate::{
+    upgrades::GlobalUpgrade, utils::resources::SelectionsPool, DefendingState, RewindRunEvent,
+};
 
 pub fn plugin(app: &mut App) {
     app.init_resource::<AbilityUpgradePool>()
         .register_type::<AbilityHolder>()
-        .add_systems(Update, abilities_activation);
+        // Dying or paused players don't get to swing their swords
+        .add_systems(
+            Update,
+            abilities_activation.run_if(not(
+                in_state(DefendingState::Dying).or(in_state(DefendingState::Paused))
+            )),
+        )
+        .add_observer(remove_abilities);
+}
+
+fn remove_abilities(
+    _trigger: Trigger<RewindRunEvent>,
+    q_holder: Query<Entity, With<AbilityHolder>>,
+    mut commands: Commands,
+) {
+    for entity in q_holder.iter() {
+        commands.entity(entity).remove::<AbilityHolder>();
+    }
 }
 
 #[derive(Resource, Default)]
@@ -17,6 +36,7 @@ pub struct AbilityUpgradePool(pub SelectionsPool<GlobalUpgrade>);
 #[derive(Clone, Reflect)]
 #[reflect(from_reflect = false)]
 pub struct Ability {
+    pub id: &'static str,
     pub cooldown: Duration, // TODO: But don't I want the duration to be configurable per instance of the same ability type?
     #[reflect(ignore)]
     pub activate: SystemId<In<Entity>, ()>,
@@ -27,12 +47,16 @@ pub struct Ability {
     // pub hotkey: KeyCode // TODO: What if I have 2 abilities of the same type (on separate cooldowns)? Incredibly freaking out of scope, but still
 }
 
+/// Presses this close to the end of the cooldown are remembered, and activate the ability once it's ready.
+const INPUT_BUFFER: Duration = Duration::from_millis(100);
+
 #[derive(Component, InspectorOptions, Reflect)]
 #[reflect(Component, InspectorOptions)]
 #[reflect(from_reflect = false)]
 pub struct AbilityHolder {
     ability: Ability,
     cooldown: Timer,
+    buffered_activation: bool,
 }
 
 impl AbilityHolder {
@@ -40,6 +64,7 @@ impl AbilityHolder {
         Self {
             cooldown: Timer::new(ability.cooldown, TimerMode::Once),
             ability,
+            buffered_activation: false,
         }
     }
 
@@ -50,6 +75,11 @@ impl AbilityHolder {
     pub fn cooldown(&self) -> &Timer {
         &self.cooldown
     }
+
+    /// Brings the ability closer to being ready again, for example as a reward.
+    pub fn refund_cooldown(&mut self, amount: Duration) {
+        self.cooldown.tick(amount);
+    }
 }
 
 #[derive(Event)]
@@ -64,11 +94,14 @@ fn abilities_activation(
     for (entity, mut holder) in q_holder.iter_mut() {
         holder.cooldown.tick(time.delta());
         if input.just_pressed(KeyCode::Space) {
-            println!("Ability");
-            if holder.cooldown.finished() {
-                holder.cooldown.reset();
-                commands.run_system_with(holder.ability.activate, entity);
+            if holder.cooldown.remaining() <= INPUT_BUFFER {
+                holder.buffered_activation = true;
             }
         }
+        if holder.buffered_activation && holder.cooldown.finished() {
+            holder.buffered_activation = false;
+            holder.cooldown.reset();
+            commands.run_system_with(holder.ability.activate, entity);
+        }
     }
 }
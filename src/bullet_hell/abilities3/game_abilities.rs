@@ -5,15 +5,18 @@ use bevy::{ecs::system::BoxedSystem, prelude::*};
 
 use crate::{
     bullet_hell::{
-        dash::{start_dashing, Dasher},
+        dash::{start_dashing, DashStrike, Dasher},
         player::ControllablePlayerFilter,
         sword::spawn_sword,
     },
-    upgrades::{GlobalUpgrade, Upgrade, UpgradesReceiverFaction},
+    ui::level_transition::ShopType,
+    upgrades::{
+        AppliedGlobalUpgrades, GlobalUpgrade, Upgrade, UpgradeRegistry, UpgradesReceiverFaction,
+    },
     utils::input::get_input_direction,
 };
 
-use super::ability_lib::{self as lib, AbilityUpgradePool};
+use super::ability_lib as lib;
 
 pub fn plugin(app: &mut App) {
     app.add_systems(Startup, initialize_ability_upgrades_pool);
@@ -22,16 +25,19 @@ pub fn plugin(app: &mut App) {
 fn dash_system(
     entity: In<Entity>,
     mut commands: Commands,
-    query: Query<(&Dasher, &CollisionLayers), ControllablePlayerFilter>,
+    query: Query<(&Dasher, Option<&DashStrike>, &CollisionLayers), ControllablePlayerFilter>,
     input: Res<ButtonInput<KeyCode>>,
 ) {
-    println!("Dash");
     // TODO: Is it okay for this to reference the user input?
-    let (dasher, collision_groups) = query.get(*entity).unwrap();
+    // A buffered activation can land while we're still being controlled externally
+    let Ok((dasher, dash_strike, collision_groups)) = query.get(*entity) else {
+        return;
+    };
     start_dashing(
         *entity,
         get_input_direction(&input),
         dasher,
+        dash_strike,
         collision_groups,
         &mut commands,
     );
@@ -42,6 +48,7 @@ fn initialize_ability_upgrades_pool(world: &mut World) {
     let parry_id = world.register_system(spawn_sword);
     let abilities = [
         lib::Ability {
+            id: "dash",
             activate: dash_id,
             cooldown: Duration::from_secs(1),
             description: "Dash",
@@ -49,6 +56,7 @@ fn initialize_ability_upgrades_pool(world: &mut World) {
             icon_texture: Path::new("sprites/upgrades/minecart.png"),
         },
         lib::Ability {
+            id: "parry",
             activate: parry_id,
             cooldown: Duration::from_secs_f32(0.5),
             description: "Cool sword",
@@ -60,6 +68,7 @@ fn initialize_ability_upgrades_pool(world: &mut World) {
         .iter()
         .map(|ability| GlobalUpgrade {
             upgrade: Upgrade {
+                id: ability.id,
                 apply_upgrade: world
                     .register_boxed_system(SetAbility::new(ability.clone()).into_boxed_system()), // TODO: Remove clone
                 name: ability.name,
@@ -70,9 +79,10 @@ fn initialize_ability_upgrades_pool(world: &mut World) {
         })
         .collect();
 
-    let mut upgrade_pool = world.resource_mut::<AbilityUpgradePool>();
+    // They're added to the shop's pool once they're unlocked
+    let mut registry = world.resource_mut::<UpgradeRegistry>();
     for upgrade in upgrades {
-        upgrade_pool.0.add_item(upgrade);
+        registry.register(upgrade, ShopType::Abilities);
     }
 }
 
@@ -88,15 +98,33 @@ impl SetAbility {
         Self { ability }
     }
 
-    fn apply(&self, In(entity): In<Entity>, mut commands: Commands) {
+    fn apply(
+        &self,
+        In(entity): In<Entity>,
+        q_holders: Query<&lib::AbilityHolder>,
+        mut applied_upgrades: ResMut<AppliedGlobalUpgrades>,
+        mut commands: Commands,
+    ) {
+        // There's one ability at a time, so the replaced one isn't owned anymore (for synergies etc.)
+        if let Ok(holder) = q_holders.get(entity) {
+            let replaced = holder.ability().id;
+            if replaced != self.ability.id {
+                applied_upgrades
+                    .applied_upgrades
+                    .retain(|upgrade| upgrade.upgrade.id != replaced);
+            }
+        }
         commands
             .entity(entity)
             .insert(lib::AbilityHolder::new(self.ability.clone()));
     }
 
     fn into_boxed_system(self) -> BoxedSystem<In<Entity>, ()> {
-        let system_function = move |In(entity): In<Entity>, commands: Commands| {
-            self.apply(In(entity), commands);
+        let system_function = move |In(entity): In<Entity>,
+                                    q_holders: Query<&lib::AbilityHolder>,
+                                    applied_upgrades: ResMut<AppliedGlobalUpgrades>,
+                                    commands: Commands| {
+            self.apply(In(entity), q_holders, applied_upgrades, commands);
         };
 
         Box::new(IntoSystem::into_system(system_function))
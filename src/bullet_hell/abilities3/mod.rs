@@ -5,7 +5,8 @@ mod game_abilities;
 mod parry;
 
 pub use ability_lib::{AbilityHolder, AbilityUpgradePool, OnAbilityChange};
+pub use parry::{ParryEvent, ParryKind, TensionPoints};
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins((ability_lib::plugin, game_abilities::plugin));
+    app.add_plugins((ability_lib::plugin, game_abilities::plugin, parry::plugin));
 }
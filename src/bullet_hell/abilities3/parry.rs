@@ -1 +1,105 @@
+use std::time::Duration;
 
+use bevy::prelude::*;
+use bevy_inspector_egui::prelude::*;
+
+use crate::bullet_hell::health::Invulnerability;
+
+use super::ability_lib::AbilityHolder;
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<ParryEvent>()
+        .register_type::<TensionPoints>()
+        .add_systems(Update, reward_parry);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParryKind {
+    /// The bullet was destroyed.
+    Normal,
+    /// The parry was timed well enough for the bullet to be reflected back.
+    Perfect,
+    /// Something that can't be destroyed (like a laser) was blocked.
+    Blocked,
+}
+
+#[derive(Event)]
+pub struct ParryEvent {
+    pub parrier: Entity,
+    #[allow(dead_code)]
+    pub parried: Entity,
+    pub kind: ParryKind,
+}
+
+/// Deltarune's TP - gained by parrying.
+#[derive(Component, InspectorOptions, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct TensionPoints {
+    pub points: f32,
+    pub max_points: f32,
+}
+
+impl TensionPoints {
+    pub fn new(max_points: f32) -> Self {
+        Self {
+            points: 0.,
+            max_points,
+        }
+    }
+
+    pub fn add(&mut self, amount: f32) {
+        self.points = (self.points + amount).min(self.max_points);
+    }
+}
+
+struct ParryReward {
+    tension: f32,
+    invulnerability: Duration,
+    cooldown_refund: Duration,
+}
+
+impl ParryReward {
+    fn for_kind(kind: ParryKind) -> Self {
+        match kind {
+            ParryKind::Normal => Self {
+                tension: 2.,
+                invulnerability: Duration::ZERO,
+                cooldown_refund: Duration::from_secs_f32(0.1),
+            },
+            ParryKind::Perfect => Self {
+                tension: 5.,
+                invulnerability: Duration::from_secs_f32(0.3),
+                cooldown_refund: Duration::from_secs_f32(0.5),
+            },
+            ParryKind::Blocked => Self {
+                tension: 1.,
+                invulnerability: Duration::ZERO,
+                cooldown_refund: Duration::ZERO,
+            },
+        }
+    }
+}
+
+fn reward_parry(
+    mut parry_events: EventReader<ParryEvent>,
+    mut q_parrier: Query<(Option<&mut TensionPoints>, Option<&mut AbilityHolder>)>,
+    mut commands: Commands,
+) {
+    for event in parry_events.read() {
+        let Ok((tension, ability_holder)) = q_parrier.get_mut(event.parrier) else {
+            continue;
+        };
+        let reward = ParryReward::for_kind(event.kind);
+        if let Some(mut tension) = tension {
+            tension.add(reward.tension);
+        }
+        if let Some(mut ability_holder) = ability_holder {
+            ability_holder.refund_cooldown(reward.cooldown_refund);
+        }
+        if !reward.invulnerability.is_zero() {
+            commands
+                .entity(event.parrier)
+                .insert(Invulnerability::new(reward.invulnerability));
+        }
+    }
+}
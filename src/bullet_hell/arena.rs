@@ -1,6 +1,8 @@
 use avian2d::prelude::*;
 use bevy::prelude::*;
 
+use crate::RewindRunEvent;
+
 use super::physics_layers;
 // use bevy::sprite::MaterialMesh2dBundle;
 // use bevy_rapier2d::prelude::*;
@@ -9,7 +11,74 @@ pub struct ArenaPlugin;
 
 impl Plugin for ArenaPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_arena);
+        app.init_resource::<Arena>()
+            .add_systems(Startup, spawn_arena)
+            .add_systems(Update, resize_arena.run_if(resource_changed::<Arena>))
+            .add_observer(reset_arena);
+    }
+}
+
+const BORDER_WIDTH: f32 = 5.;
+
+/// The shape of the box the player moves in. Changing it moves the walls.
+#[derive(Resource)]
+pub struct Arena {
+    pub size: Vec2,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self {
+            size: Vec2::splat(100.),
+        }
+    }
+}
+
+fn reset_arena(_trigger: Trigger<RewindRunEvent>, mut arena: ResMut<Arena>) {
+    *arena = Arena::default();
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum ArenaWall {
+    Floor,
+    Left,
+    Right,
+    Roof,
+}
+
+impl ArenaWall {
+    const ALL: [ArenaWall; 4] = [Self::Floor, Self::Left, Self::Right, Self::Roof];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Floor => "Arena.Floor",
+            Self::Left => "Arena.Left",
+            Self::Right => "Arena.Right",
+            Self::Roof => "Arena.Roof",
+        }
+    }
+
+    /// The wall's size and position for an arena of the given size.
+    fn placement(&self, arena_size: Vec2) -> (Vec2, Vec2) {
+        let offset = arena_size / 2. - BORDER_WIDTH / 2.;
+        match self {
+            Self::Floor => (
+                Vec2::new(arena_size.x, BORDER_WIDTH),
+                Vec2::new(0., -offset.y),
+            ),
+            Self::Left => (
+                Vec2::new(BORDER_WIDTH, arena_size.y),
+                Vec2::new(-offset.x, 0.),
+            ),
+            Self::Right => (
+                Vec2::new(BORDER_WIDTH, arena_size.y),
+                Vec2::new(offset.x, 0.),
+            ),
+            Self::Roof => (
+                Vec2::new(arena_size.x, BORDER_WIDTH),
+                Vec2::new(0., offset.y),
+            ),
+        }
     }
 }
 
@@ -17,57 +86,36 @@ fn spawn_arena(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    arena: Res<Arena>,
 ) {
-    let size = 100.;
-    let border_width = 5.;
-    let position_offset = size / 2. - border_width / 2.;
     commands.spawn((Name::new("Arena"), Transform::from_xyz(0., 10., 0.)));
-    commands.spawn((
-        Name::new("Arena.Floor"),
-        Mesh2d(meshes.add(bevy::math::primitives::Rectangle::new(size, border_width).mesh())),
-        MeshMaterial2d(materials.add(ColorMaterial::from(Color::WHITE))),
-        Transform::from_xyz(0., -position_offset, 0.),
-        Collider::rectangle(size, border_width),
-        CollisionLayers::new(
-            physics_layers::GameLayers::Wall,
-            physics_layers::GameLayers::all_bits(),
-        ),
-        RigidBody::Static,
-    ));
-    commands.spawn((
-        Name::new("Arena.Left"),
-        Mesh2d(meshes.add(bevy::math::primitives::Rectangle::new(border_width, size).mesh())),
-        MeshMaterial2d(materials.add(ColorMaterial::from(Color::WHITE))),
-        Transform::from_xyz(-position_offset, 0., 0.),
-        Collider::rectangle(border_width, size),
-        CollisionLayers::new(
-            physics_layers::GameLayers::Wall,
-            physics_layers::GameLayers::all_bits(),
-        ),
-        RigidBody::Static,
-    ));
-    commands.spawn((
-        Name::new("Arena.Right"),
-        Mesh2d(meshes.add(bevy::math::primitives::Rectangle::new(border_width, size).mesh())),
-        MeshMaterial2d(materials.add(ColorMaterial::from(Color::WHITE))),
-        Transform::from_xyz(position_offset, 0., 0.),
-        Collider::rectangle(border_width, size),
-        CollisionLayers::new(
-            physics_layers::GameLayers::Wall,
-            physics_layers::GameLayers::all_bits(),
-        ),
-        RigidBody::Static,
-    ));
-    commands.spawn((
-        Name::new("Arena.Roof"),
-        Mesh2d(meshes.add(bevy::math::primitives::Rectangle::new(size, border_width).mesh())),
-        MeshMaterial2d(materials.add(ColorMaterial::from(Color::WHITE))),
-        Transform::from_xyz(0., position_offset, 0.),
-        Collider::rectangle(size, border_width),
-        CollisionLayers::new(
-            physics_layers::GameLayers::Wall,
-            physics_layers::GameLayers::all_bits(),
-        ),
-        RigidBody::Static,
-    ));
+    for wall in ArenaWall::ALL {
+        let (size, position) = wall.placement(arena.size);
+        commands.spawn((
+            Name::new(wall.name()),
+            wall,
+            Mesh2d(meshes.add(bevy::math::primitives::Rectangle::new(size.x, size.y).mesh())),
+            MeshMaterial2d(materials.add(ColorMaterial::from(Color::WHITE))),
+            Transform::from_translation(position.extend(0.)),
+            Collider::rectangle(size.x, size.y),
+            CollisionLayers::new(
+                physics_layers::GameLayers::Wall,
+                physics_layers::GameLayers::all_bits(),
+            ),
+            RigidBody::Static,
+        ));
+    }
+}
+
+fn resize_arena(
+    arena: Res<Arena>,
+    mut q_walls: Query<(&ArenaWall, &mut Mesh2d, &mut Collider, &mut Transform)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (wall, mut mesh, mut collider, mut transform) in q_walls.iter_mut() {
+        let (size, position) = wall.placement(arena.size);
+        mesh.0 = meshes.add(bevy::math::primitives::Rectangle::new(size.x, size.y).mesh());
+        *collider = Collider::rectangle(size.x, size.y);
+        transform.translation = position.extend(transform.translation.z);
+    }
 }
@@ -2,22 +2,43 @@ use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_inspector_egui::prelude::ReflectInspectorOptions;
 use bevy_inspector_egui::InspectorOptions;
+use serde::Deserialize;
 
 use crate::{
     upgrades::{UpgradesReceiver, UpgradesReceiverFaction},
-    AppState,
+    AppState, RewindRunEvent,
 };
 
-use super::{game_z_index, health::TryDamageEvent, physics_layers, player::Player};
+use super::{
+    enemies::Enemy,
+    game_z_index,
+    health::{DamageType, TryDamageEvent},
+    physics_layers,
+    player::Player,
+};
 
 pub struct BulletsPlugin;
 
 impl Plugin for BulletsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            FixedUpdate,
-            player_collision.run_if(in_state(AppState::Defending)),
-        );
+        app.register_type::<PlayerBullet>()
+            .add_event::<GrazeEvent>()
+            .add_systems(Update, detect_grazes.run_if(in_state(AppState::Defending)))
+            .add_systems(
+                FixedUpdate,
+                (player_collision, player_bullet_collision).run_if(in_state(AppState::Defending)),
+            )
+            .add_observer(despawn_bullets);
+    }
+}
+
+fn despawn_bullets(
+    _trigger: Trigger<RewindRunEvent>,
+    q_bullets: Query<Entity, Or<(With<Bullet>, With<PlayerBullet>)>>,
+    mut commands: Commands,
+) {
+    for entity in q_bullets.iter() {
+        commands.entity(entity).despawn();
     }
 }
 
@@ -28,8 +49,68 @@ pub struct Bullet {
     pub direction: Vec3,
     pub damage: f32,
     pub speed: f32,
+    /// The enemy that shot the bullet.
+    pub source: Option<Entity>,
+}
+
+/// How close a bullet has to pass by the player to count as a graze.
+const GRAZE_DISTANCE: f32 = 15.;
+
+/// Sent when a bullet leaves the grazing distance of the player without hitting them.
+#[derive(Event)]
+pub struct GrazeEvent {
+    pub grazer: Entity,
+    pub bullet: Entity,
 }
 
+/// Set on bullets while they're within grazing distance of someone.
+#[derive(Component)]
+struct Grazing {
+    grazer: Entity,
+}
+
+fn detect_grazes(
+    q_bullets: Query<(Entity, &Transform, Option<&Grazing>), With<Bullet>>,
+    q_players: Query<(Entity, &Transform), With<Player>>,
+    mut graze_writer: EventWriter<GrazeEvent>,
+    mut commands: Commands,
+) {
+    for (player_entity, player_transform) in q_players.iter() {
+        for (bullet_entity, bullet_transform, grazing) in q_bullets.iter() {
+            let distance = player_transform
+                .translation
+                .xy()
+                .distance(bullet_transform.translation.xy());
+            match grazing {
+                None if distance < GRAZE_DISTANCE => {
+                    commands.entity(bullet_entity).try_insert(Grazing {
+                        grazer: player_entity,
+                    });
+                }
+                // Bullets that hit are despawned before they get to leave
+                Some(grazing) if grazing.grazer == player_entity && distance >= GRAZE_DISTANCE => {
+                    commands.entity(bullet_entity).try_remove::<Grazing>();
+                    graze_writer.write(GrazeEvent {
+                        grazer: player_entity,
+                        bullet: bullet_entity,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A bullet that belongs to the player, and hurts enemies instead of the player (for example a parried bullet).
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct PlayerBullet {
+    pub damage: f32,
+    /// Whoever sent the bullet back, who gets the credit for the damage.
+    pub source: Option<Entity>,
+}
+
+#[derive(Clone, Default, Reflect, Deserialize)]
 pub struct BulletProperties {
     pub damage: f32,
     pub size: f32,
@@ -40,6 +121,7 @@ pub fn spawn_bullet_in_pos(
     position: Vec3,
     direction: Vec3,
     properties: BulletProperties,
+    source: Option<Entity>,
     commands: &mut Commands,
 ) {
     // TODO: Question - when do I receive asset_server as a parameter, and when do I get it from the world?
@@ -59,6 +141,7 @@ pub fn spawn_bullet_in_pos(
                 direction,
                 damage: properties.damage,
                 speed: properties.speed,
+                source,
             },
             UpgradesReceiver {
                 factions: UpgradesReceiverFaction::EnemyBullets,
@@ -114,6 +197,36 @@ fn player_collision(
         damage_events.write(TryDamageEvent {
             target_entity: player_entity,
             damage: bullet_component.damage,
+            damage_type: DamageType::Bullet,
+            source: bullet_component.source,
+        });
+    }
+}
+
+fn player_bullet_collision(
+    mut commands: Commands,
+    mut contact_events: EventReader<CollisionStarted>,
+    bullets: Query<(Entity, &PlayerBullet)>,
+    enemies: Query<Entity, With<Enemy>>,
+    mut damage_events: EventWriter<TryDamageEvent>,
+) {
+    for event in contact_events.read() {
+        let CollisionStarted(entity1, entity2) = event;
+        let (enemy_entity, (bullet_entity, bullet_component)) =
+            if let (Ok(enemy), Ok(bullet)) = (enemies.get(*entity1), bullets.get(*entity2)) {
+                (enemy, bullet)
+            } else if let (Ok(enemy), Ok(bullet)) = (enemies.get(*entity2), bullets.get(*entity1)) {
+                (enemy, bullet)
+            } else {
+                continue;
+            };
+        commands.entity(bullet_entity).despawn();
+
+        damage_events.write(TryDamageEvent {
+            target_entity: enemy_entity,
+            damage: bullet_component.damage,
+            damage_type: DamageType::Bullet,
+            source: bullet_component.source,
         });
     }
 }
@@ -6,30 +6,89 @@ use bevy_inspector_egui::prelude::*;
 
 use crate::{
     bullet_hell::{
-        health::Invulnerability,
+        bullet::Bullet,
+        enemies::Enemy,
+        game_z_index,
+        health::{DamageImmunity, DamageType, TryDamageEvent},
         physics_layers::GameLayers,
         player::{ControllablePlayerFilter, ControlledExternally},
     },
     utils::input::get_input_direction,
-    AppState,
+    AppState, RewindRunEvent,
 };
 
 pub struct DashPlugin;
 
 impl Plugin for DashPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (handle_active_dash).run_if(in_state(AppState::Defending)),
-        );
+        app.register_type::<Dasher>()
+            .register_type::<DashStrike>()
+            .add_event::<DashStartedEvent>()
+            .add_systems(
+                Update,
+                (
+                    track_last_direction,
+                    handle_active_dash,
+                    spawn_afterimages,
+                    fade_afterimages,
+                )
+                    .run_if(in_state(AppState::Defending)),
+            )
+            .add_systems(
+                FixedUpdate,
+                dash_strike_collision.run_if(in_state(AppState::Defending)),
+            )
+            .add_observer(reset_dashes);
     }
 }
 
+#[derive(Event)]
+pub struct DashStartedEvent {
+    pub dasher: Entity,
+}
+
+/// Cuts short any dash that was still going, and takes away the upgrades' `DashStrike`.
+fn reset_dashes(
+    _trigger: Trigger<RewindRunEvent>,
+    q_dashes: Query<(Entity, &ActiveDash)>,
+    q_strikes: Query<Entity, With<DashStrike>>,
+    q_afterimages: Query<Entity, With<Afterimage>>,
+    mut commands: Commands,
+) {
+    for (entity, dash) in q_dashes.iter() {
+        commands
+            .entity(entity)
+            .remove::<(ControlledExternally, ActiveDash)>()
+            .insert(dash.original_collision_groups);
+    }
+    for entity in q_strikes.iter() {
+        commands.entity(entity).remove::<DashStrike>();
+    }
+    for entity in q_afterimages.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+const DASH_IMMUNITY: &str = "Dash";
+const AFTERIMAGE_INTERVAL: Duration = Duration::from_millis(30);
+const AFTERIMAGE_LIFETIME: Duration = Duration::from_millis(200);
+const AFTERIMAGE_ALPHA: f32 = 0.5;
+
 #[derive(Component, InspectorOptions, Default, Reflect)]
 #[reflect(Component, InspectorOptions)]
 pub struct Dasher {
     pub dash_amount: f32,
     pub dash_speed: f32,
+    /// The direction we dash in when there is no input direction.
+    pub last_direction: Vec3,
+}
+
+/// Makes dashes hurt whatever they pass through. Granted by upgrades.
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct DashStrike {
+    pub damage: f32,
+    pub destroys_bullets: bool,
 }
 
 // // TODO: Add the hit_effects stuff to a system_set/schedule that will happen after the gameplay ones
@@ -58,31 +117,45 @@ pub fn start_dashing(
     entity: Entity,
     direction: Vec3,
     dasher: &Dasher,
+    dash_strike: Option<&DashStrike>,
     original_collision_groups: &CollisionLayers,
     commands: &mut Commands,
 ) {
-    if direction != Vec3::ZERO {
-        let dash_duration = Duration::from_secs_f32(dasher.dash_amount / dasher.dash_speed);
-        commands.entity(entity).insert((
-            ActiveDash::new(
-                dash_duration,
-                (direction * dasher.dash_speed).xy(),
-                *original_collision_groups,
-            ),
-            ControlledExternally::new("Dash"),
-            Invulnerability::new(dash_duration),
-            CollisionLayers::new(
-                original_collision_groups.memberships,
-                original_collision_groups.filters & !LayerMask::from(GameLayers::Bullet),
-            ),
-        ));
+    let direction = if direction != Vec3::ZERO {
+        direction
+    } else {
+        dasher.last_direction
+    };
+    if direction == Vec3::ZERO {
+        return;
     }
+
+    // When the dash destroys bullets, we still need to collide with them
+    let filters = if dash_strike.is_some_and(|strike| strike.destroys_bullets) {
+        original_collision_groups.filters
+    } else {
+        original_collision_groups.filters & !LayerMask::from(GameLayers::Bullet)
+    };
+    let dash_duration = Duration::from_secs_f32(dasher.dash_amount / dasher.dash_speed);
+    commands.entity(entity).insert((
+        ActiveDash::new(
+            dash_duration,
+            (direction * dasher.dash_speed).xy(),
+            *original_collision_groups,
+        ),
+        ControlledExternally::new("Dash"),
+        CollisionLayers::new(original_collision_groups.memberships, filters),
+    ));
+    // An immunity rather than an `Invulnerability`, which would cut a longer one short
+    DamageImmunity::add(&mut commands.entity(entity), DASH_IMMUNITY);
+    commands.send_event(DashStartedEvent { dasher: entity });
 }
 
 #[derive(Component, InspectorOptions, Default, Reflect)]
 #[reflect(Component, InspectorOptions)]
 struct ActiveDash {
     timer: Timer,
+    afterimage_timer: Timer,
     linear_velocity: Vec2,
     original_collision_groups: CollisionLayers,
 }
@@ -96,6 +169,7 @@ impl ActiveDash {
     ) -> Self {
         Self {
             timer: Timer::new(dash_duration, TimerMode::Once),
+            afterimage_timer: Timer::new(AFTERIMAGE_INTERVAL, TimerMode::Repeating),
             linear_velocity,
             original_collision_groups,
         }
@@ -113,11 +187,108 @@ fn handle_active_dash(
 
         dash.timer.tick(time.delta());
         if dash.timer.just_finished() {
-            commands
-                .entity(entity)
+            let mut entity_commands = commands.entity(entity);
+            entity_commands
                 .remove::<ControlledExternally>()
                 .remove::<ActiveDash>()
                 .insert(dash.original_collision_groups);
+            DamageImmunity::remove(&mut entity_commands, DASH_IMMUNITY);
+        }
+    }
+}
+
+fn track_last_direction(
+    mut query: Query<&mut Dasher, ControllablePlayerFilter>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    let direction = get_input_direction(&input);
+    if direction != Vec3::ZERO {
+        for mut dasher in query.iter_mut() {
+            dasher.last_direction = direction;
+        }
+    }
+}
+
+/// A fading copy of a dashing entity, left behind as a trail.
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+struct Afterimage {
+    lifetime: Timer,
+}
+
+fn spawn_afterimages(
+    mut query: Query<(
+        &mut ActiveDash,
+        &Transform,
+        &Mesh2d,
+        &MeshMaterial2d<ColorMaterial>,
+    )>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (mut dash, transform, mesh, material) in query.iter_mut() {
+        dash.afterimage_timer.tick(time.delta());
+        if !dash.afterimage_timer.just_finished() {
+            continue;
+        }
+        let Some(color) = materials.get(material).map(|material| material.color) else {
+            continue;
+        };
+        commands.spawn((
+            Mesh2d(mesh.0.clone()),
+            MeshMaterial2d(materials.add(color.with_alpha(AFTERIMAGE_ALPHA))),
+            transform.with_translation(transform.translation.with_z(game_z_index::AFTERIMAGES)),
+            Afterimage {
+                lifetime: Timer::new(AFTERIMAGE_LIFETIME, TimerMode::Once),
+            },
+            Name::new("Afterimage"),
+        ));
+    }
+}
+
+fn fade_afterimages(
+    mut query: Query<(Entity, &mut Afterimage, &MeshMaterial2d<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut afterimage, material) in query.iter_mut() {
+        afterimage.lifetime.tick(time.delta());
+        if let Some(material) = materials.get_mut(material) {
+            material
+                .color
+                .set_alpha(AFTERIMAGE_ALPHA * afterimage.lifetime.fraction_remaining());
+        }
+        if afterimage.lifetime.just_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn dash_strike_collision(
+    mut commands: Commands,
+    mut contact_events: EventReader<CollisionStarted>,
+    dashers: Query<&DashStrike, With<ActiveDash>>,
+    enemies: Query<(), With<Enemy>>,
+    bullets: Query<(), With<Bullet>>,
+    mut damage_events: EventWriter<TryDamageEvent>,
+) {
+    for CollisionStarted(entity1, entity2) in contact_events.read() {
+        for (dasher_entity, other_entity) in [(*entity1, *entity2), (*entity2, *entity1)] {
+            let Ok(dash_strike) = dashers.get(dasher_entity) else {
+                continue;
+            };
+            if enemies.contains(other_entity) {
+                damage_events.write(TryDamageEvent {
+                    target_entity: other_entity,
+                    damage: dash_strike.damage,
+                    damage_type: DamageType::Dash,
+                    source: Some(dasher_entity),
+                });
+            } else if dash_strike.destroys_bullets && bullets.contains(other_entity) {
+                commands.entity(other_entity).try_despawn();
+            }
         }
     }
 }
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    ui::lose_screen::LoseEvent, utils::resources::GameFreeze, AppState, DefendingState,
+    ResetRunEvent,
+};
+
+use super::{
+    bullet::{Bullet, PlayerBullet},
+    health::{Health, Invulnerability},
+    player::Player,
+};
+
+const DEATH_ANIMATION_DURATION: Duration = Duration::from_millis(1500);
+const DEATH_SPINS: f32 = 2.;
+/// The part of the max health the player comes back with.
+const REVIVE_HEALTH: f32 = 0.5;
+/// The enemy bullets this close to the player are cleared when they revive, so they get a chance.
+const REVIVE_CLEAR_RADIUS: f32 = 60.;
+const REVIVE_INVULNERABILITY: Duration = Duration::from_secs(2);
+const DEATH_FREEZE: &str = "death";
+
+/// What happens when the player runs out of health: the game freezes while they die, and then
+/// they either revive or lose.
+pub fn plugin(app: &mut App) {
+    app.init_resource::<ReviveUsed>()
+        .add_observer(reset_revive_used)
+        .add_systems(
+            Update,
+            detect_death.run_if(in_state(DefendingState::Fighting)),
+        )
+        .add_systems(OnEnter(DefendingState::Dying), start_dying)
+        .add_systems(
+            Update,
+            death_animation.run_if(in_state(DefendingState::Dying)),
+        )
+        // The game stays frozen behind the defeat screen, however the fight was lost
+        .add_systems(OnEnter(AppState::Defeat), freeze_game)
+        .add_systems(OnExit(AppState::Defeat), unfreeze_game);
+}
+
+/// Brings the player back once when they die, instead of losing.
+#[derive(Component)]
+pub struct Revive;
+
+/// The player already came back once this run, so re-applying the upgrades doesn't give the
+/// `Revive` back.
+#[derive(Resource, Default)]
+pub struct ReviveUsed(pub bool);
+
+/// The player is dying. Plays on the real time, since the rest of the game is frozen.
+#[derive(Component)]
+struct DeathAnimation {
+    timer: Timer,
+}
+
+fn detect_death(
+    q_players: Query<&Health, With<Player>>,
+    mut defending_state: ResMut<NextState<DefendingState>>,
+) {
+    if q_players.iter().any(|health| health.health <= 0.) {
+        defending_state.set(DefendingState::Dying);
+    }
+}
+
+fn start_dying(q_players: Query<(Entity, &Health), With<Player>>, mut commands: Commands) {
+    commands.run_system_cached(freeze_game);
+    for (entity, health) in q_players.iter() {
+        if health.health <= 0. {
+            commands.entity(entity).insert(DeathAnimation {
+                timer: Timer::new(DEATH_ANIMATION_DURATION, TimerMode::Once),
+            });
+        }
+    }
+}
+
+fn death_animation(
+    mut q_dying: Query<(Entity, &mut DeathAnimation, &mut Transform, Has<Revive>)>,
+    real_time: Res<Time<Real>>,
+    mut commands: Commands,
+    mut lose_event: EventWriter<LoseEvent>,
+) {
+    for (entity, mut animation, mut transform, has_revive) in q_dying.iter_mut() {
+        animation.timer.tick(real_time.delta());
+        let progress = animation.timer.fraction();
+        transform.rotation = Quat::from_rotation_z(progress * DEATH_SPINS * std::f32::consts::TAU);
+        transform.scale = Vec3::splat(1. - progress);
+        if !animation.timer.just_finished() {
+            continue;
+        }
+        commands.entity(entity).remove::<DeathAnimation>();
+        if has_revive {
+            commands.run_system_cached_with(revive, entity);
+        } else {
+            lose_event.write(LoseEvent);
+        }
+    }
+}
+
+fn revive(
+    In(entity): In<Entity>,
+    mut q_players: Query<(&mut Health, &mut Transform), With<Player>>,
+    q_bullets: Query<(Entity, &Transform), (With<Bullet>, Without<PlayerBullet>, Without<Player>)>,
+    mut defending_state: ResMut<NextState<DefendingState>>,
+    mut revive_used: ResMut<ReviveUsed>,
+    mut commands: Commands,
+) {
+    let Ok((mut health, mut transform)) = q_players.get_mut(entity) else {
+        return;
+    };
+    revive_used.0 = true;
+    health.health = health.max_health * REVIVE_HEALTH;
+    transform.rotation = Quat::IDENTITY;
+    transform.scale = Vec3::ONE;
+    for (bullet, bullet_transform) in q_bullets.iter() {
+        let distance = bullet_transform
+            .translation
+            .xy()
+            .distance(transform.translation.xy());
+        if distance < REVIVE_CLEAR_RADIUS {
+            commands.entity(bullet).try_despawn();
+        }
+    }
+    commands
+        .entity(entity)
+        .remove::<Revive>()
+        .insert(Invulnerability::new(REVIVE_INVULNERABILITY));
+    defending_state.set(DefendingState::Fighting);
+    GameFreeze::remove(&mut commands, DEATH_FREEZE);
+}
+
+fn reset_revive_used(_trigger: Trigger<ResetRunEvent>, mut revive_used: ResMut<ReviveUsed>) {
+    revive_used.0 = false;
+}
+
+fn freeze_game(mut commands: Commands) {
+    GameFreeze::add(&mut commands, DEATH_FREEZE);
+}
+
+fn unfreeze_game(mut commands: Commands) {
+    GameFreeze::remove(&mut commands, DEATH_FREEZE);
+}
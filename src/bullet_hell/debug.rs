@@ -1,7 +1,9 @@
 use bevy::prelude::*;
+use rand::seq::IteratorRandom;
 
 use super::{
-    health::{Health, TryDamageEvent},
+    enemies::registry::{EnemyRegistry, SpawnEnemy},
+    health::{DamageType, Health, TryDamageEvent},
     level::CombatFinishedEvent,
     player::Player,
     sword::spawn_sword,
@@ -18,6 +20,7 @@ impl Plugin for DebugPlugin {
                 skip_level,
                 debug_spawn_sword,
                 make_player_invincible,
+                debug_spawn_enemy,
             ),
         );
     }
@@ -32,6 +35,8 @@ fn apply_damage(
         damage_writer.write(TryDamageEvent {
             target_entity: player.single()?,
             damage: 5.,
+            damage_type: DamageType::Other,
+            source: None,
         });
     }
     Ok(())
@@ -66,3 +71,15 @@ fn make_player_invincible(
 
     Ok(())
 }
+
+fn debug_spawn_enemy(
+    input: Res<ButtonInput<KeyCode>>,
+    registry: Res<EnemyRegistry>,
+    mut commands: Commands,
+) {
+    if input.just_pressed(KeyCode::KeyE) {
+        if let Some(id) = registry.ids().choose(&mut rand::thread_rng()) {
+            commands.queue(SpawnEnemy::new(id.clone(), Vec2::new(0., 30.), Vec2::NEG_Y));
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::path::Path;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    game_config::{Difficulty, GameConfig},
+    upgrades::{GlobalUpgrade, Upgrade, UpgradeApplier, UpgradeRegistry, UpgradesReceiverFaction},
+    ResetRunEvent,
+};
+
+use super::{
+    bullet::Bullet,
+    enemies::{laser::LaserCannon, BulletSpeedMultiplier, ContactDamage, FireRateMultiplier},
+};
+
+const DIFFICULTY_ID: &str = "difficulty";
+const LEVEL_SCALING_ID: &str = "level_scaling";
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<DifficultyLevel>()
+        .add_systems(Startup, register_scaling_upgrades)
+        .add_observer(reset_difficulty_level);
+}
+
+/// How much stronger the enemies are, as multipliers of their stats.
+#[derive(Clone, Copy)]
+struct StatMultipliers {
+    bullet_speed: f32,
+    fire_rate: f32,
+    damage: f32,
+}
+
+/// How hard the run starts, and how much harder every level after the first one gets.
+pub struct DifficultyCurve {
+    base: StatMultipliers,
+    per_level: StatMultipliers,
+    enemies_per_level: usize,
+    /// Every this many levels, one more enemy joins at the end of each level.
+    extra_enemy_every: Option<usize>,
+}
+
+impl DifficultyCurve {
+    pub fn new(difficulty: Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Easy => Self {
+                base: StatMultipliers {
+                    bullet_speed: 0.8,
+                    fire_rate: 0.8,
+                    damage: 0.75,
+                },
+                per_level: StatMultipliers {
+                    bullet_speed: 1.03,
+                    fire_rate: 1.03,
+                    damage: 1.05,
+                },
+                enemies_per_level: 1,
+                extra_enemy_every: None,
+            },
+            Difficulty::Normal => Self {
+                base: StatMultipliers {
+                    bullet_speed: 1.,
+                    fire_rate: 1.,
+                    damage: 1.,
+                },
+                per_level: StatMultipliers {
+                    bullet_speed: 1.05,
+                    fire_rate: 1.05,
+                    damage: 1.1,
+                },
+                enemies_per_level: 1,
+                extra_enemy_every: Some(4),
+            },
+            Difficulty::Hard => Self {
+                base: StatMultipliers {
+                    bullet_speed: 1.2,
+                    fire_rate: 1.2,
+                    damage: 1.25,
+                },
+                per_level: StatMultipliers {
+                    bullet_speed: 1.08,
+                    fire_rate: 1.08,
+                    damage: 1.15,
+                },
+                enemies_per_level: 1,
+                extra_enemy_every: Some(2),
+            },
+        }
+    }
+
+    /// How many enemies join once `level` is finished.
+    pub fn enemies_after_level(&self, level: usize) -> usize {
+        self.enemies_per_level + self.extra_enemy_every.map_or(0, |every| level / every)
+    }
+}
+
+/// The number of the level being played (starting from 1), as far as the difficulty is concerned.
+#[derive(Resource, Default)]
+pub struct DifficultyLevel(pub usize);
+
+fn reset_difficulty_level(_trigger: Trigger<ResetRunEvent>, mut level: ResMut<DifficultyLevel>) {
+    level.0 = 0;
+}
+
+/// Makes the enemies as strong as they should be in `level`.
+/// The scaling is applied as enemy upgrades, so that it stacks with the ones the player picks,
+/// and carries over to enemies that spawn later.
+pub struct ScaleDifficulty {
+    pub level: usize,
+}
+
+impl Command for ScaleDifficulty {
+    fn apply(self, world: &mut World) {
+        world.resource_mut::<DifficultyLevel>().0 = self.level;
+        let id = if self.level <= 1 {
+            DIFFICULTY_ID
+        } else {
+            LEVEL_SCALING_ID
+        };
+        let Some(upgrade) = world.resource::<UpgradeRegistry>().get(id).cloned() else {
+            error!("The difficulty upgrade {} isn't registered", id);
+            return;
+        };
+        let apply_upgrade = world.resource::<UpgradeApplier>().apply_upgrade_to_all;
+        if let Err(e) = world.run_system_with(apply_upgrade, upgrade) {
+            error!("Failed to scale the difficulty: {}", e);
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct EnemyStats<'w, 's> {
+    q_fire_rates: Query<'w, 's, &'static mut FireRateMultiplier>,
+    q_bullet_speeds: Query<'w, 's, &'static mut BulletSpeedMultiplier>,
+    q_laser_cannons: Query<'w, 's, &'static mut LaserCannon>,
+    q_contact_damage: Query<'w, 's, &'static mut ContactDamage>,
+    q_bullets: Query<'w, 's, &'static mut Bullet>,
+}
+
+impl EnemyStats<'_, '_> {
+    fn scale(&mut self, entity: Entity, multipliers: StatMultipliers) {
+        if let Ok(mut fire_rate) = self.q_fire_rates.get_mut(entity) {
+            fire_rate.0 *= multipliers.fire_rate;
+        }
+        // The cannons shoot their bullets this much faster, so the bullets themselves aren't sped up
+        if let Ok(mut bullet_speed) = self.q_bullet_speeds.get_mut(entity) {
+            bullet_speed.0 *= multipliers.bullet_speed;
+        }
+        if let Ok(mut laser_cannon) = self.q_laser_cannons.get_mut(entity) {
+            laser_cannon.scale_damage(multipliers.damage);
+        }
+        if let Ok(mut contact_damage) = self.q_contact_damage.get_mut(entity) {
+            contact_damage.damage *= multipliers.damage;
+        }
+        if let Ok(mut bullet) = self.q_bullets.get_mut(entity) {
+            bullet.damage *= multipliers.damage;
+        }
+    }
+}
+
+fn scaling_upgrade(
+    world: &mut World,
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    multipliers: StatMultipliers,
+) -> GlobalUpgrade {
+    GlobalUpgrade {
+        upgrade: Upgrade {
+            id,
+            apply_upgrade: world.register_system(
+                move |In(entity): In<Entity>, mut stats: EnemyStats| {
+                    stats.scale(entity, multipliers);
+                },
+            ),
+            name,
+            description,
+            icon_texture: Path::new("sprites/upgrades/yellow.png"),
+        },
+        receiver_factions: UpgradesReceiverFaction::Enemy | UpgradesReceiverFaction::EnemyBullets,
+    }
+}
+
+fn register_scaling_upgrades(world: &mut World) {
+    let curve = DifficultyCurve::new(world.resource::<GameConfig>().difficulty);
+    let upgrades = [
+        scaling_upgrade(
+            world,
+            DIFFICULTY_ID,
+            "Difficulty",
+            "The enemies are as strong as the difficulty says",
+            curve.base,
+        ),
+        scaling_upgrade(
+            world,
+            LEVEL_SCALING_ID,
+            "Stronger Enemies",
+            "The enemies get stronger with every level",
+            curve.per_level,
+        ),
+    ];
+    let mut registry = world.resource_mut::<UpgradeRegistry>();
+    for upgrade in upgrades {
+        registry.register_hidden(upgrade);
+    }
+}
@@ -13,7 +13,7 @@ impl LevelTransitionEffectsPool {
         self.effects.push(effect);
     }
 
-    pub fn get_random(&self) -> &Effect {
-        &self.effects[rand::thread_rng().gen_range(0..self.effects.len())]
+    pub fn get_random(&self, rng: &mut impl Rng) -> &Effect {
+        &self.effects[rng.gen_range(0..self.effects.len())]
     }
 }
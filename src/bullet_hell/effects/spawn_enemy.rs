@@ -4,14 +4,11 @@ use bevy::prelude::*;
 
 use rand::Rng;
 
-use crate::bullet_hell::enemies::moving_cannon::{
-    spawn_cannon, spawn_stationary_cannon, CannonSpawnProperties,
-};
+use crate::{bullet_hell::enemies::registry::SpawnEnemy, utils::resources::RunRng};
 
-use super::{
-    effect::{Effect, LevelTransitionEffectsPool},
-    spawning_animation::SpawningAnimation,
-};
+use super::effect::{Effect, LevelTransitionEffectsPool};
+
+const SPAWNING_ANIMATION_DURATION: Duration = Duration::from_millis(500);
 
 pub struct SpawnEnemyPlugin;
 
@@ -26,6 +23,9 @@ pub fn register_effects(world: &mut World) {
     let effects = [
         Effect(world.register_system(spawn_cannon_on_the_right)),
         Effect(world.register_system(spawn_random_stationary_cannon)),
+        Effect(world.register_system(spawn_random_laser_cannon)),
+        Effect(world.register_system(spawn_random_chaser)),
+        Effect(world.register_system(spawn_patrolling_cannon)),
     ];
     for e in effects {
         let mut effects_res = world
@@ -35,35 +35,64 @@ pub fn register_effects(world: &mut World) {
     }
 }
 
-pub fn spawn_cannon_on_the_right(mut commands: Commands, asset_server: Res<AssetServer>) {
-    spawn_cannon(
-        CannonSpawnProperties {
-            start: Vec3::new(100., -40., 0.),
-            end: Vec3::new(100., 40., 0.),
-            shooting_direction: -Vec3::X,
-        },
-        SpawningAnimation::new(Duration::from_millis(500)),
-        &asset_server,
-        &mut commands,
-    )
+pub fn spawn_cannon_on_the_right(mut commands: Commands) {
+    commands.queue(
+        SpawnEnemy::new("cannon", Vec2::new(100., 0.), Vec2::NEG_X)
+            .with_spawning_animation(SPAWNING_ANIMATION_DURATION),
+    );
+}
+
+pub fn spawn_random_stationary_cannon(mut run_rng: ResMut<RunRng>, mut commands: Commands) {
+    let options = [
+        (Vec2::new(100., 70.), Vec2::new(-1., -1.)),
+        (Vec2::new(-100., 70.), Vec2::new(1., -1.)),
+        (Vec2::new(100., -70.), Vec2::new(-1., 1.)),
+        (Vec2::new(-100., -70.), Vec2::new(1., 1.)),
+    ];
+    let (position, facing) = options[run_rng.rng().gen_range(0..options.len())];
+    commands.queue(
+        SpawnEnemy::new("stationary_cannon", position, facing)
+            .with_spawning_animation(SPAWNING_ANIMATION_DURATION),
+    );
 }
 
-pub fn spawn_random_stationary_cannon(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn spawn_random_laser_cannon(mut run_rng: ResMut<RunRng>, mut commands: Commands) {
     let options = [
-        (Vec3::new(100., 70., 0.), Vec3::new(-1., -1., 0.)),
-        (Vec3::new(-100., 70., 0.), Vec3::new(1., -1., 0.)),
-        (Vec3::new(100., -70., 0.), Vec3::new(-1., 1., 0.)),
-        (Vec3::new(-100., -70., 0.), Vec3::new(1., 1., 0.)),
+        // Sweeps across the arena from the left side
+        ("sweeping_laser_cannon", Vec2::new(-100., 0.), Vec2::X),
+        // Aims at the player from the right side
+        ("tracking_laser_cannon", Vec2::new(100., 0.), Vec2::NEG_X),
+        // Fires a fan of beams from below
+        ("fan_laser_cannon", Vec2::new(0., -70.), Vec2::Y),
     ];
-    let selected = options.get(rand::thread_rng().gen_range(0..4)).unwrap();
-    spawn_stationary_cannon(
-        CannonSpawnProperties {
-            start: selected.0,
-            end: Vec3::ZERO,
-            shooting_direction: selected.1,
-        },
-        SpawningAnimation::new(Duration::from_millis(500)),
-        &asset_server,
-        &mut commands,
-    )
+    let (id, position, facing) = options[run_rng.rng().gen_range(0..options.len())];
+    commands.queue(
+        SpawnEnemy::new(id, position, facing).with_spawning_animation(SPAWNING_ANIMATION_DURATION),
+    );
+}
+
+pub fn spawn_random_chaser(mut run_rng: ResMut<RunRng>, mut commands: Commands) {
+    let corners = [
+        Vec2::new(-35., -35.),
+        Vec2::new(35., -35.),
+        Vec2::new(-35., 35.),
+        Vec2::new(35., 35.),
+    ];
+    let rng = run_rng.rng();
+    let id = if rng.gen_bool(0.5) {
+        "chaser"
+    } else {
+        "orbiter"
+    };
+    commands.queue(
+        SpawnEnemy::new(id, corners[rng.gen_range(0..corners.len())], Vec2::NEG_Y)
+            .with_spawning_animation(SPAWNING_ANIMATION_DURATION),
+    );
+}
+
+pub fn spawn_patrolling_cannon(mut commands: Commands) {
+    commands.queue(
+        SpawnEnemy::new("patrolling_cannon", Vec2::new(0., -70.), Vec2::Y)
+            .with_spawning_animation(SPAWNING_ANIMATION_DURATION),
+    );
 }
@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    bullet_hell::{
+        arena::Arena,
+        health::Health,
+        level::CurrentLevelConfig,
+        player::{Player, SoulMode},
+    },
+    utils::resources::GameFreeze,
+    AppState, DefendingState,
+};
+
+use super::registry::{
+    insert_behaviour, remove_behaviour, EnemyBehaviour, MovementDefinition, SpawnEnemy,
+};
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Defending), spawn_level_boss)
+            .add_systems(OnExit(AppState::Defending), unpause_phase_transitions)
+            .add_systems(
+                Update,
+                (
+                    boss_phase_triggers,
+                    // Waits behind the dying player and the pause menu
+                    phase_transition_animation.run_if(in_state(DefendingState::Fighting)),
+                    boss_defeated.before(super::enemy_death),
+                )
+                    .run_if(in_state(AppState::Defending)),
+            );
+    }
+}
+
+/// Where the boss of a boss level spawns - above the arena, facing down.
+const BOSS_POSITION: Vec2 = Vec2::new(0., 70.);
+const PHASE_TRANSITION_DURATION: Duration = Duration::from_millis(1500);
+const PHASE_TRANSITION_FREEZE: &str = "phase_transition";
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum PhaseTrigger {
+    /// When the boss' health drops below this fraction of its max health.
+    HealthBelow(f32),
+    /// This many seconds after the previous phase started.
+    After(f32),
+}
+
+/// The optional parts stay as they were in the previous phase.
+#[derive(Clone, Deserialize)]
+pub struct BossPhase {
+    pub trigger: PhaseTrigger,
+    pub behaviour: EnemyBehaviour,
+    #[serde(default)]
+    pub movement: Option<MovementDefinition>,
+    #[serde(default)]
+    pub arena_size: Option<Vec2>,
+    #[serde(default)]
+    pub soul_mode: Option<SoulMode>,
+}
+
+#[derive(Component)]
+pub struct Boss {
+    phases: Vec<BossPhase>,
+    next_phase: usize,
+    /// Seconds since the current phase started.
+    phase_time: f32,
+    position: Vec2,
+    facing: Vec2,
+    size: f32,
+}
+
+impl Boss {
+    pub fn new(phases: Vec<BossPhase>, position: Vec2, facing: Vec2, size: f32) -> Self {
+        Self {
+            phases,
+            next_phase: 0,
+            phase_time: 0.,
+            position,
+            facing,
+            size,
+        }
+    }
+
+    fn triggered_phase(&self, health: &Health) -> Option<&BossPhase> {
+        let phase = self.phases.get(self.next_phase)?;
+        let triggered = match phase.trigger {
+            PhaseTrigger::HealthBelow(fraction) => health.health <= health.max_health * fraction,
+            PhaseTrigger::After(seconds) => self.phase_time >= seconds,
+        };
+        triggered.then_some(phase)
+    }
+}
+
+/// The boss is changing phases. The rest of the game is paused while this plays.
+#[derive(Component)]
+struct PhaseTransition {
+    timer: Timer,
+    phase: BossPhase,
+}
+
+fn spawn_level_boss(level_config: Res<CurrentLevelConfig>, mut commands: Commands) {
+    if let Some(id) = level_config.0.boss {
+        commands.queue(
+            SpawnEnemy::new(id, BOSS_POSITION, Vec2::NEG_Y)
+                .with_spawning_animation(Duration::from_millis(500)),
+        );
+    }
+}
+
+fn boss_phase_triggers(
+    mut q_bosses: Query<(Entity, &mut Boss, &Health), Without<PhaseTransition>>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut boss, health) in q_bosses.iter_mut() {
+        boss.phase_time += time.delta_secs();
+        let Some(phase) = boss.triggered_phase(health).cloned() else {
+            continue;
+        };
+        boss.next_phase += 1;
+        boss.phase_time = 0.;
+        commands.entity(entity).insert(PhaseTransition {
+            timer: Timer::new(PHASE_TRANSITION_DURATION, TimerMode::Once),
+            phase,
+        });
+        // Freezes the bullets (and everything else) until the transition is done
+        GameFreeze::add(&mut commands, PHASE_TRANSITION_FREEZE);
+    }
+}
+
+fn phase_transition_animation(
+    mut q_transitions: Query<(Entity, &Boss, &mut PhaseTransition, &mut Transform)>,
+    real_time: Res<Time<Real>>,
+    mut commands: Commands,
+) {
+    for (entity, boss, mut transition, mut transform) in q_transitions.iter_mut() {
+        transition.timer.tick(real_time.delta());
+        let pulse = (transition.timer.fraction() * std::f32::consts::TAU * 3.).sin();
+        transform.scale = Vec3::splat(1. + 0.3 * pulse.abs());
+
+        if transition.timer.just_finished() {
+            transform.scale = Vec3::ONE;
+            let phase = transition.phase.clone();
+            let (position, facing, size) = (boss.position, boss.facing, boss.size);
+            commands.entity(entity).remove::<PhaseTransition>();
+            commands.queue(move |world: &mut World| {
+                apply_phase(world, entity, phase, position, facing, size);
+            });
+            GameFreeze::remove(&mut commands, PHASE_TRANSITION_FREEZE);
+        }
+    }
+}
+
+fn apply_phase(
+    world: &mut World,
+    entity: Entity,
+    phase: BossPhase,
+    position: Vec2,
+    facing: Vec2,
+    size: f32,
+) {
+    if let Some(arena_size) = phase.arena_size {
+        world.resource_mut::<Arena>().size = arena_size;
+        // Don't leave the player stuck outside of a shrinking arena
+        let bounds = arena_size / 2. - Vec2::splat(10.);
+        let mut q_players = world.query_filtered::<&mut Transform, With<Player>>();
+        for mut transform in q_players.iter_mut(world) {
+            let clamped = transform.translation.xy().clamp(-bounds, bounds);
+            transform.translation = clamped.extend(transform.translation.z);
+        }
+    }
+    if let Some(soul_mode) = phase.soul_mode {
+        let mut q_souls = world.query_filtered::<&mut SoulMode, With<Player>>();
+        for mut mode in q_souls.iter_mut(world) {
+            *mode = soul_mode;
+        }
+    }
+
+    let Ok(mut entity) = world.get_entity_mut(entity) else {
+        return;
+    };
+    remove_behaviour(&mut entity);
+    insert_behaviour(&mut entity, phase.behaviour, facing, size);
+    if let Some(movement) = phase.movement {
+        let path = movement.to_path(position, facing);
+        if let Some(mut transform) = entity.get_mut::<Transform>() {
+            transform.translation = path.position_at(0.).extend(transform.translation.z);
+        }
+        entity.insert(path);
+    }
+}
+
+/// Undoes the boss' changes to the arena and the player. Winning the level is up to its `WinCondition`.
+fn boss_defeated(
+    q_bosses: Query<&Health, With<Boss>>,
+    mut q_souls: Query<&mut SoulMode, With<Player>>,
+    mut arena: ResMut<Arena>,
+) {
+    for health in q_bosses.iter() {
+        if health.health <= 0. {
+            *arena = Arena::default();
+            for mut soul_mode in q_souls.iter_mut() {
+                *soul_mode = SoulMode::Red;
+            }
+        }
+    }
+}
+
+/// In case the level ended mid-transition (for example by skipping it).
+fn unpause_phase_transitions(mut commands: Commands) {
+    GameFreeze::remove(&mut commands, PHASE_TRANSITION_FREEZE);
+}
@@ -0,0 +1,109 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_inspector_egui::prelude::*;
+
+use serde::Deserialize;
+
+use crate::{
+    bullet_hell::{physics_layers, player::Player},
+    utils::kinematic_controller::KinematicController,
+    AppState,
+};
+
+use super::ContactDamage;
+
+pub struct ChaserPlugin;
+
+impl Plugin for ChaserPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Chaser>().add_systems(
+            Update,
+            chaser_steering.run_if(in_state(AppState::Defending)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, InspectorOptions, Reflect, Deserialize)]
+#[reflect(InspectorOptions)]
+pub enum SteeringBehaviour {
+    /// Head straight for the player.
+    Chase,
+    /// Circle around the player at a fixed distance.
+    Orbit { radius: f32 },
+}
+
+/// An enemy that moves around inside the arena, steering towards the player.
+#[derive(Component, InspectorOptions, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct Chaser {
+    pub behaviour: SteeringBehaviour,
+    #[inspector(min = 0.0)]
+    pub max_speed: f32,
+    /// How quickly the chaser can change its velocity, which makes it overshoot and drift.
+    #[inspector(min = 0.0)]
+    pub acceleration: f32,
+}
+
+fn desired_velocity(behaviour: SteeringBehaviour, to_player: Vec2, max_speed: f32) -> Vec2 {
+    let direction = match behaviour {
+        SteeringBehaviour::Chase => to_player.normalize_or_zero(),
+        SteeringBehaviour::Orbit { radius } => {
+            let radial = to_player.normalize_or_zero();
+            // Move along the circle, while correcting the distance towards the radius
+            let radial_error = ((to_player.length() - radius) / radius).clamp(-1., 1.);
+            (radial.perp() + radial * radial_error).normalize_or_zero()
+        }
+    };
+    direction * max_speed
+}
+
+fn chaser_steering(
+    mut q_chasers: Query<(&Chaser, &Transform, &mut LinearVelocity)>,
+    q_player: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = q_player.single() else {
+        return;
+    };
+    for (chaser, transform, mut velocity) in q_chasers.iter_mut() {
+        let to_player = (player_transform.translation - transform.translation).xy();
+        let desired = desired_velocity(chaser.behaviour, to_player, chaser.max_speed);
+        let steering =
+            (desired - velocity.0).clamp_length_max(chaser.acceleration * time.delta_secs());
+        velocity.0 += steering;
+    }
+}
+
+/// Everything a chaser needs besides its sprite and health.
+pub fn chaser_bundle(
+    behaviour: SteeringBehaviour,
+    max_speed: f32,
+    acceleration: f32,
+    contact_damage: f32,
+    size: f32,
+) -> impl Bundle {
+    (
+        Chaser {
+            behaviour,
+            max_speed,
+            acceleration,
+        },
+        ContactDamage {
+            damage: contact_damage,
+        },
+        KinematicController,
+        (
+            CollisionLayers::new(
+                physics_layers::GameLayers::Enemy,
+                [
+                    physics_layers::GameLayers::Wall,
+                    physics_layers::GameLayers::Player,
+                    physics_layers::GameLayers::PlayerBullet,
+                ],
+            ),
+            Collider::rectangle(size, size),
+            RigidBody::Kinematic,
+            LinearVelocity::default(),
+        ),
+    )
+}
@@ -3,42 +3,128 @@ use std::time::Duration;
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_inspector_egui::prelude::*;
+use serde::Deserialize;
 // use bevy_rapier2d::prelude::*;
 
 use crate::{
-    bullet_hell::{game_z_index, health::TryDamageEvent, physics_layers, player::Player},
+    bullet_hell::{
+        game_z_index,
+        health::{DamageType, TryDamageEvent},
+        physics_layers,
+        player::Player,
+        sword::Parrying,
+    },
     AppState,
 };
 
+use super::{FireRateMultiplier, LaserDurationMultiplier};
+
 pub struct LaserPlugin;
 
 impl Plugin for LaserPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_initial_laser_cannons)
-            .add_systems(
-                Update,
-                (
-                    laser_cannon_behavior,
-                    laser_lifecycle,
-                    laser_player_collision,
-                )
-                    .run_if(in_state(AppState::Defending)),
+        app.add_systems(
+            Update,
+            (
+                laser_cannon_behavior,
+                laser_lifecycle,
+                (track_player, laser_rotation).chain(),
+                laser_player_damage,
             )
-            .register_type::<LaserCannon>()
-            .register_type::<Laser>()
-            .register_type::<LaserState>();
+                .run_if(in_state(AppState::Defending)),
+        )
+        .register_type::<LaserCannon>()
+        .register_type::<LaserPattern>()
+        .register_type::<LaserDamageCurve>()
+        .register_type::<Laser>()
+        .register_type::<LaserState>();
+    }
+}
+
+/// Describes the beams a laser cannon fires. Angles are in radians, where 0 points straight down.
+#[derive(Clone, InspectorOptions, Reflect, Deserialize)]
+#[reflect(InspectorOptions)]
+#[serde(default)]
+pub struct LaserPattern {
+    pub aim: f32,
+    /// The offset of each beam from `aim` - more than one makes a multi-beam cannon.
+    pub beam_offsets: Vec<f32>,
+    /// How much the beams rotate over the course of their active phase.
+    pub sweep: f32,
+    /// Whether the cannon keeps aiming at the player while winding up.
+    pub tracks_player: bool,
+    pub damage: LaserDamageCurve,
+}
+
+/// The damage of a laser tick, as a function of how far into the active phase the laser is.
+#[derive(Clone, InspectorOptions, Reflect, Deserialize)]
+#[reflect(InspectorOptions)]
+pub enum LaserDamageCurve {
+    Constant(f32),
+    /// Goes from `start` to `end` over the active phase.
+    Eased {
+        start: f32,
+        end: f32,
+        ease: EaseFunction,
+    },
+}
+
+impl Default for LaserDamageCurve {
+    fn default() -> Self {
+        Self::Constant(5.)
+    }
+}
+
+impl LaserDamageCurve {
+    fn scale(&mut self, factor: f32) {
+        match self {
+            Self::Constant(damage) => *damage *= factor,
+            Self::Eased { start, end, .. } => {
+                *start *= factor;
+                *end *= factor;
+            }
+        }
+    }
+
+    fn sample(&self, active_fraction: f32) -> f32 {
+        match self {
+            Self::Constant(damage) => *damage,
+            Self::Eased { start, end, ease } => {
+                start.lerp(*end, ease.sample_clamped(active_fraction))
+            }
+        }
+    }
+}
+
+impl Default for LaserPattern {
+    fn default() -> Self {
+        Self {
+            aim: 0.,
+            beam_offsets: vec![0.],
+            sweep: 0.,
+            tracks_player: false,
+            damage: LaserDamageCurve::default(),
+        }
     }
 }
 
 #[derive(Component, InspectorOptions, Default, Reflect)]
 #[reflect(Component, InspectorOptions)]
-struct LaserCannon {
+pub struct LaserCannon {
     // TODO: Timer duplication with moving_cannon
     // Do I want to have a component with a one-shot callback for when the timer is done?
     // Maybe have a component with just the timer, and a system to check whenever it's done?
     // So for (repeated_attacks, laser) in query { if repeated_attacks.just_finished {laser.shoot()}}
     shooting_timer: Timer,
     has_active_laser: bool,
+    pattern: LaserPattern,
+}
+
+impl LaserCannon {
+    /// Only affects the lasers fired from now on.
+    pub fn scale_damage(&mut self, factor: f32) {
+        self.pattern.damage.scale(factor);
+    }
 }
 
 #[derive(Component, InspectorOptions, Default, Reflect)]
@@ -46,17 +132,43 @@ struct LaserCannon {
 struct Shooting {}
 
 fn laser_cannon_behavior(
-    mut query: Query<(Entity, &mut LaserCannon)>,
+    mut query: Query<(
+        Entity,
+        &mut LaserCannon,
+        Option<&FireRateMultiplier>,
+        Option<&LaserDurationMultiplier>,
+    )>,
     time: Res<Time>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    for (entity, mut laser_cannon) in query.iter_mut() {
+    for (entity, mut laser_cannon, fire_rate, laser_duration) in query.iter_mut() {
         if !laser_cannon.has_active_laser {
-            laser_cannon.shooting_timer.tick(time.delta());
+            let fire_rate = fire_rate.map_or(1., |fire_rate| fire_rate.0);
+            laser_cannon
+                .shooting_timer
+                .tick(time.delta().mul_f32(fire_rate));
             if laser_cannon.shooting_timer.just_finished() {
-                spawn_laser(entity, &mut commands, &mut meshes, &mut materials);
+                let active_duration = Laser::default()
+                    .active_duration
+                    .mul_f32(laser_duration.map_or(1., |laser_duration| laser_duration.0));
+                for angle_offset in laser_cannon.pattern.beam_offsets.iter() {
+                    spawn_laser(
+                        entity,
+                        Laser {
+                            aim: laser_cannon.pattern.aim,
+                            angle_offset: *angle_offset,
+                            sweep: laser_cannon.pattern.sweep,
+                            damage: laser_cannon.pattern.damage.clone(),
+                            active_duration,
+                            ..Default::default()
+                        },
+                        &mut commands,
+                        &mut meshes,
+                        &mut materials,
+                    );
+                }
                 laser_cannon.has_active_laser = true
             }
         }
@@ -65,12 +177,18 @@ fn laser_cannon_behavior(
 
 #[derive(Component, InspectorOptions, Reflect)]
 #[reflect(Component, InspectorOptions)]
-struct Laser {
+pub struct Laser {
     // TODO: probably a curve and not just a duration
     winding_up_duration: Duration,
     active_duration: Duration,
     winding_down_duration: Duration,
-    damage: f32,
+    damage: LaserDamageCurve,
+    /// Damage is dealt every time this finishes, while the laser is active.
+    damage_tick: Timer,
+    /// Same as `LaserPattern::aim`, but can change while tracking the player.
+    aim: f32,
+    angle_offset: f32,
+    sweep: f32,
 }
 
 impl Default for Laser {
@@ -79,11 +197,26 @@ impl Default for Laser {
             winding_up_duration: Duration::from_secs(1),
             active_duration: Duration::from_secs(2),
             winding_down_duration: Duration::from_secs_f32(0.25),
-            damage: 5.,
+            damage: LaserDamageCurve::default(),
+            damage_tick: Timer::from_seconds(0.1, TimerMode::Repeating),
+            aim: 0.,
+            angle_offset: 0.,
+            sweep: 0.,
         }
     }
 }
 
+impl Laser {
+    fn angle(&self, state: &LaserState) -> f32 {
+        let swept = match state {
+            LaserState::WindingUp(_) => 0.,
+            LaserState::Active(timer) => timer.fraction(),
+            LaserState::WindingDown(_) => 1.,
+        };
+        self.aim + self.angle_offset + self.sweep * swept
+    }
+}
+
 #[derive(Component, InspectorOptions, Reflect)]
 #[reflect(Component, InspectorOptions)]
 enum LaserState {
@@ -117,21 +250,34 @@ impl LaserState {
     }
 }
 
+const LASER_WIDTH: f32 = 10.;
+const LASER_LENGTH: f32 = 1000.;
+
+/// The beam starts at the cannon and extends in the direction of `angle`.
+fn laser_transform(angle: f32) -> Transform {
+    let rotation = Quat::from_rotation_z(angle);
+    Transform {
+        translation: (rotation * Vec3::new(0., -LASER_LENGTH / 2., 0.))
+            .with_z(game_z_index::LASERS),
+        rotation,
+        ..Default::default()
+    }
+}
+
 fn spawn_laser(
     parent: Entity,
+    laser: Laser,
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
 ) {
-    const WIDTH: f32 = 10.;
-    const HEIGHT: f32 = 1000.;
-    let laser = Laser::default();
+    let state = LaserState::new(&laser);
     let child = commands
         .spawn((
-            Mesh2d(meshes.add(Rectangle::new(WIDTH, HEIGHT))), // TODO: custom width, full height (TODO: rotations),
+            Mesh2d(meshes.add(Rectangle::new(LASER_WIDTH, LASER_LENGTH))), // TODO: custom width, full height (TODO: curves),
             MeshMaterial2d(materials.add(Color::WHITE.with_alpha(0.))),
-            Transform::from_xyz(0., -HEIGHT / 2., game_z_index::LASERS),
-            LaserState::new(&laser),
+            laser_transform(laser.angle(&state)),
+            state,
             laser,
             Name::new("Laser"),
             (
@@ -140,14 +286,42 @@ fn spawn_laser(
                     physics_layers::GameLayers::all_bits(),
                 ),
                 RigidBody::Static,
-                Collider::rectangle(WIDTH, HEIGHT),
+                Collider::rectangle(LASER_WIDTH, LASER_LENGTH),
                 Sensor,
+                // Only hurts while active
+                ColliderDisabled,
             ),
         ))
         .id();
     commands.entity(parent).add_child(child);
 }
 
+fn track_player(
+    cannon_query: Query<(&LaserCannon, &GlobalTransform)>,
+    mut laser_query: Query<(&ChildOf, &mut Laser, &LaserState)>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+) {
+    let Ok(player_transform) = player_query.single() else {
+        return;
+    };
+    for (child_of, mut laser, state) in laser_query.iter_mut() {
+        let Ok((cannon, cannon_transform)) = cannon_query.get(child_of.parent()) else {
+            continue;
+        };
+        if cannon.pattern.tracks_player && matches!(state, LaserState::WindingUp(_)) {
+            let direction = player_transform.translation() - cannon_transform.translation();
+            // The angle for which rotating `NEG_Y` gives `direction`
+            laser.aim = direction.x.atan2(-direction.y);
+        }
+    }
+}
+
+fn laser_rotation(mut laser_query: Query<(&Laser, &LaserState, &mut Transform)>) {
+    for (laser, state, mut transform) in laser_query.iter_mut() {
+        *transform = laser_transform(laser.angle(state));
+    }
+}
+
 fn laser_lifecycle(
     mut cannon_query: Query<&mut LaserCannon>,
     mut laser_query: Query<(
@@ -176,14 +350,14 @@ fn laser_lifecycle(
                 lerp_color(0., 1., timer.fraction());
 
                 if timer.just_finished() {
-                    // commands.entity(entity).remove::<ColliderDisabled>();
+                    commands.entity(entity).remove::<ColliderDisabled>();
                     *state = LaserState::make_active(laser);
                 }
             }
             LaserState::Active(ref mut timer) => {
                 timer.tick(time.delta());
                 if timer.just_finished() {
-                    // commands.entity(entity).insert(ColliderDisabled);
+                    commands.entity(entity).insert(ColliderDisabled);
                     *state = LaserState::make_winding_down(laser);
                 }
             }
@@ -194,56 +368,48 @@ fn laser_lifecycle(
                 if timer.just_finished() {
                     commands.entity(entity).despawn();
                 }
-                cannon_query
-                    .get_mut(child_of.parent())
-                    .unwrap()
-                    .has_active_laser = false;
+                // The cannon might have stopped being a laser cannon (like a boss changing phases)
+                if let Ok(mut cannon) = cannon_query.get_mut(child_of.parent()) {
+                    cannon.has_active_laser = false;
+                }
             }
         }
     }
 }
 
-fn laser_player_collision(
-    mut contact_events: EventReader<CollisionStarted>,
-    lasers: Query<&Laser>,
-    players: Query<Entity, With<Player>>,
+fn laser_player_damage(
+    mut lasers: Query<(Entity, &mut Laser, &LaserState, &ChildOf)>,
+    players: Query<(Entity, &CollidingEntities), (With<Player>, Without<Parrying>)>,
+    time: Res<Time>,
     mut damage_writer: EventWriter<TryDamageEvent>,
 ) {
-    for event in contact_events.read() {
-        let CollisionStarted(entity1, entity2) = event;
-        // TODO: get this working with swapped entity orders???
-        println!("Collision: {entity1} {entity2}");
-        let (player_entity, bullet_component) =
-            if let (Ok(player), Ok(enemy)) = (players.get(*entity1), lasers.get(*entity2)) {
-                (player, enemy)
-            } else if let (Ok(player), Ok(enemy)) = (players.get(*entity2), lasers.get(*entity1)) {
-                (player, enemy)
-            } else {
-                return;
-            };
-
-        // TODO: make this an event? Who is responsible for handling it? what would it achieve?
-        damage_writer.write(TryDamageEvent {
-            target_entity: player_entity,
-            damage: bullet_component.damage,
-        });
-    }
-}
-
-fn spawn_initial_laser_cannons(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let position = Vec3::new(0., 70., game_z_index::CANNONS);
-    let sprite_size = 7.5;
-    let texture = asset_server.load("character.png");
-    commands.spawn((
-        Transform::from_translation(position),
-        Sprite {
-            custom_size: Some(Vec2::new(sprite_size, sprite_size)),
-            ..Sprite::from_image(texture)
-        },
-        LaserCannon {
-            shooting_timer: Timer::from_seconds(1., TimerMode::Repeating),
-            has_active_laser: false,
-        },
-        Name::new("Laser Cannon"),
-    ));
+    for (laser_entity, mut laser, state, child_of) in lasers.iter_mut() {
+        let LaserState::Active(active_timer) = state else {
+            continue;
+        };
+        laser.damage_tick.tick(time.delta());
+        if !laser.damage_tick.just_finished() {
+            continue;
+        }
+        let damage = laser.damage.sample(active_timer.fraction());
+        for (player_entity, colliding_entities) in players.iter() {
+            // Invulnerability is handled by the health module
+            if colliding_entities.contains(&laser_entity) {
+                damage_writer.write(TryDamageEvent {
+                    target_entity: player_entity,
+                    damage,
+                    damage_type: DamageType::Laser,
+                    source: Some(child_of.parent()),
+                });
+            }
+        }
+    }
+}
+
+pub fn laser_cannon_bundle(pattern: LaserPattern, shooting_interval: Duration) -> impl Bundle {
+    LaserCannon {
+        shooting_timer: Timer::new(shooting_interval, TimerMode::Repeating),
+        has_active_laser: false,
+        pattern,
+    }
 }
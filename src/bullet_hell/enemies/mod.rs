@@ -1,2 +1,157 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_inspector_egui::prelude::*;
+
+use crate::{AppState, DefendingState, ResetRunEvent};
+
+use super::{
+    health::{handle_damage, DamageType, Health, TryDamageEvent},
+    physics_layers,
+    player::Player,
+};
+
+pub mod boss;
+pub mod chaser;
 pub mod laser;
 pub mod moving_cannon;
+pub mod path;
+pub mod registry;
+#[cfg(test)]
+mod tests;
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Enemy>()
+            .register_type::<ContactDamage>()
+            .register_type::<FireRateMultiplier>()
+            .register_type::<BulletSpeedMultiplier>()
+            .register_type::<LaserDurationMultiplier>()
+            .add_plugins((
+                boss::BossPlugin,
+                chaser::ChaserPlugin,
+                path::PathPlugin,
+                registry::EnemyRegistryPlugin,
+            ))
+            .add_systems(
+                Update,
+                (
+                    enemy_death
+                        .after(handle_damage)
+                        .run_if(in_state(AppState::Defending)),
+                    // Freezing the time doesn't stop the touching enemies from hurting
+                    contact_damage.run_if(in_state(DefendingState::Fighting)),
+                ),
+            )
+            .add_observer(reset_enemies);
+    }
+}
+
+/// A marker for anything hostile that the player can hurt (for example with reflected bullets).
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct Enemy;
+
+/// Hurts the player while they touch this entity.
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct ContactDamage {
+    pub damage: f32,
+}
+
+/// How many times as often the enemy attacks, compared to its definition.
+#[derive(Component, InspectorOptions, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct FireRateMultiplier(pub f32);
+
+impl Default for FireRateMultiplier {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Scales the speed of the bullets the enemy shoots.
+#[derive(Component, InspectorOptions, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct BulletSpeedMultiplier(pub f32);
+
+impl Default for BulletSpeedMultiplier {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Scales how long the enemy's lasers stay active.
+#[derive(Component, InspectorOptions, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct LaserDurationMultiplier(pub f32);
+
+impl Default for LaserDurationMultiplier {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// The stats that enemy upgrades change, which every enemy has regardless of how it attacks.
+pub fn enemy_stats() -> impl Bundle {
+    (
+        FireRateMultiplier::default(),
+        BulletSpeedMultiplier::default(),
+        LaserDurationMultiplier::default(),
+    )
+}
+
+/// The collider that lets the player (and the player's bullets) hit an enemy.
+pub fn enemy_collider(size: f32) -> impl Bundle {
+    (
+        CollisionLayers::new(
+            physics_layers::GameLayers::Enemy,
+            [
+                physics_layers::GameLayers::PlayerBullet,
+                physics_layers::GameLayers::Player,
+            ],
+        ),
+        RigidBody::Kinematic,
+        Collider::rectangle(size, size),
+        Sensor,
+    )
+}
+
+/// Puts back the enemies a run starts with.
+fn reset_enemies(
+    _trigger: Trigger<ResetRunEvent>,
+    q_enemies: Query<Entity, With<Enemy>>,
+    mut commands: Commands,
+) {
+    for entity in q_enemies.iter() {
+        commands.entity(entity).despawn();
+    }
+    registry::spawn_initial_enemies(commands);
+}
+
+fn enemy_death(mut commands: Commands, q_enemies: Query<(Entity, &Health), With<Enemy>>) {
+    for (entity, health) in q_enemies.iter() {
+        if health.health <= 0. {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn contact_damage(
+    q_players: Query<(Entity, &CollidingEntities), With<Player>>,
+    q_damage: Query<(Entity, &ContactDamage)>,
+    mut damage_writer: EventWriter<TryDamageEvent>,
+) {
+    for (player_entity, colliding_entities) in q_players.iter() {
+        // Invulnerability is handled by the health module
+        for (entity, contact_damage) in q_damage.iter_many(colliding_entities.iter()) {
+            damage_writer.write(TryDamageEvent {
+                target_entity: player_entity,
+                damage: contact_damage.damage,
+                damage_type: DamageType::Contact,
+                source: Some(entity),
+            });
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::prelude::*;
+use serde::Deserialize;
+
+use crate::AppState;
+
+pub struct PathPlugin;
+
+impl Plugin for PathPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<FollowPath>()
+            .register_type::<PathSegment>()
+            .register_type::<PathMode>()
+            .add_systems(Update, follow_path.run_if(in_state(AppState::Defending)));
+    }
+}
+
+fn linear() -> EaseFunction {
+    EaseFunction::Linear
+}
+
+/// A piece of a path, starting where the previous segment ended. Durations are in seconds.
+#[derive(Clone, InspectorOptions, Reflect, Deserialize)]
+#[reflect(InspectorOptions)]
+pub enum PathSegment {
+    Line {
+        to: Vec2,
+        duration: f32,
+        #[serde(default = "linear")]
+        ease: EaseFunction,
+    },
+    /// A cubic bezier curve.
+    Bezier {
+        control1: Vec2,
+        control2: Vec2,
+        to: Vec2,
+        duration: f32,
+        #[serde(default = "linear")]
+        ease: EaseFunction,
+    },
+}
+
+impl PathSegment {
+    fn end(&self) -> Vec2 {
+        match self {
+            Self::Line { to, .. } | Self::Bezier { to, .. } => *to,
+        }
+    }
+
+    fn duration(&self) -> f32 {
+        match self {
+            Self::Line { duration, .. } | Self::Bezier { duration, .. } => *duration,
+        }
+    }
+
+    fn sample(&self, from: Vec2, time: f32) -> Vec2 {
+        let fraction = if self.duration() > 0. {
+            time / self.duration()
+        } else {
+            1.
+        };
+        match self {
+            Self::Line { to, ease, .. } => from.lerp(*to, ease.sample_clamped(fraction)),
+            Self::Bezier {
+                control1,
+                control2,
+                to,
+                ease,
+                ..
+            } => {
+                let t = ease.sample_clamped(fraction);
+                let u = 1. - t;
+                from * u * u * u
+                    + *control1 * 3. * u * u * t
+                    + *control2 * 3. * u * t * t
+                    + *to * t * t * t
+            }
+        }
+    }
+
+    /// Moves every point of the segment through `transform`.
+    pub fn map_points(&self, transform: impl Fn(Vec2) -> Vec2) -> Self {
+        match self.clone() {
+            Self::Line { to, duration, ease } => Self::Line {
+                to: transform(to),
+                duration,
+                ease,
+            },
+            Self::Bezier {
+                control1,
+                control2,
+                to,
+                duration,
+                ease,
+            } => Self::Bezier {
+                control1: transform(control1),
+                control2: transform(control2),
+                to: transform(to),
+                duration,
+                ease,
+            },
+        }
+    }
+}
+
+/// What happens once the end of the path is reached.
+#[derive(Clone, Copy, InspectorOptions, Default, Reflect, Deserialize)]
+#[reflect(InspectorOptions)]
+pub enum PathMode {
+    /// Stop at the end.
+    Once,
+    /// Jump back to the start - the last segment should usually end where the path starts.
+    #[default]
+    Loop,
+    /// Walk the path backwards, then forwards again.
+    PingPong,
+}
+
+/// Moves the entity along a path, by time rather than by speed, so that it's always at the
+/// same place at the same point in its cycle (which keeps it in sync with its shooting timer).
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct FollowPath {
+    pub start: Vec2,
+    pub segments: Vec<PathSegment>,
+    pub mode: PathMode,
+    elapsed: f32,
+}
+
+impl FollowPath {
+    pub fn new(start: Vec2, segments: Vec<PathSegment>, mode: PathMode) -> Self {
+        Self {
+            start,
+            segments,
+            mode,
+            elapsed: 0.,
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.segments.iter().map(PathSegment::duration).sum()
+    }
+
+    pub fn position_at(&self, time: f32) -> Vec2 {
+        let duration = self.duration();
+        if duration <= 0. {
+            return self.segments.last().map_or(self.start, PathSegment::end);
+        }
+        let time = match self.mode {
+            PathMode::Once => time.min(duration),
+            PathMode::Loop => time.rem_euclid(duration),
+            PathMode::PingPong => {
+                let time = time.rem_euclid(2. * duration);
+                if time > duration {
+                    2. * duration - time
+                } else {
+                    time
+                }
+            }
+        };
+
+        let mut from = self.start;
+        let mut remaining = time;
+        for segment in self.segments.iter() {
+            if remaining <= segment.duration() {
+                return segment.sample(from, remaining);
+            }
+            remaining -= segment.duration();
+            from = segment.end();
+        }
+        from
+    }
+}
+
+fn follow_path(time: Res<Time>, mut query: Query<(&mut FollowPath, &mut Transform)>) {
+    for (mut path, mut transform) in query.iter_mut() {
+        path.elapsed += time.delta_secs();
+        let position = path.position_at(path.elapsed);
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(to: Vec2, duration: f32) -> PathSegment {
+        PathSegment::Line {
+            to,
+            duration,
+            ease: EaseFunction::Linear,
+        }
+    }
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-4),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_path_endpoints() {
+        let path = FollowPath::new(
+            Vec2::new(-5., 0.),
+            vec![line(Vec2::ZERO, 1.), line(Vec2::new(0., 10.), 2.)],
+            PathMode::Once,
+        );
+        assert_near(path.position_at(0.), Vec2::new(-5., 0.));
+        assert_near(path.position_at(1.), Vec2::ZERO);
+        assert_near(path.position_at(2.), Vec2::new(0., 5.));
+        assert_near(path.position_at(3.), Vec2::new(0., 10.));
+        // Once it's done, it stays at the end
+        assert_near(path.position_at(10.), Vec2::new(0., 10.));
+    }
+
+    #[test]
+    fn test_bezier_midpoint() {
+        let path = FollowPath::new(
+            Vec2::ZERO,
+            vec![PathSegment::Bezier {
+                control1: Vec2::new(0., 4.),
+                control2: Vec2::new(4., 4.),
+                to: Vec2::new(4., 0.),
+                duration: 2.,
+                ease: EaseFunction::Linear,
+            }],
+            PathMode::Once,
+        );
+        assert_near(path.position_at(0.), Vec2::ZERO);
+        assert_near(path.position_at(1.), Vec2::new(2., 3.));
+        assert_near(path.position_at(2.), Vec2::new(4., 0.));
+    }
+
+    #[test]
+    fn test_loop_wraps_around() {
+        let path = FollowPath::new(
+            Vec2::ZERO,
+            vec![line(Vec2::new(10., 0.), 1.), line(Vec2::ZERO, 1.)],
+            PathMode::Loop,
+        );
+        assert_near(path.position_at(2.5), path.position_at(0.5));
+        assert_near(path.position_at(2.5), Vec2::new(5., 0.));
+        assert_near(path.position_at(3.5), Vec2::new(5., 0.));
+        assert_near(path.position_at(4.), Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_ping_pong_reverses() {
+        let path = FollowPath::new(
+            Vec2::ZERO,
+            vec![line(Vec2::new(10., 0.), 1.)],
+            PathMode::PingPong,
+        );
+        assert_near(path.position_at(0.25), Vec2::new(2.5, 0.));
+        assert_near(path.position_at(1.), Vec2::new(10., 0.));
+        // On the way back
+        assert_near(path.position_at(1.25), Vec2::new(7.5, 0.));
+        assert_near(path.position_at(2.), Vec2::ZERO);
+        assert_near(path.position_at(2.25), Vec2::new(2.5, 0.));
+    }
+}
@@ -0,0 +1,362 @@
+use std::{collections::HashMap, fs, time::Duration};
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    bullet_hell::{
+        bullet::BulletProperties, effects::spawning_animation::SpawningAnimation, game_z_index,
+        health::Health,
+    },
+    upgrades::{UpgradesReceiver, UpgradesReceiverFaction},
+    utils::kinematic_controller::KinematicController,
+};
+
+use super::{
+    boss::{Boss, BossPhase},
+    chaser::{chaser_bundle, Chaser, SteeringBehaviour},
+    enemy_collider, enemy_stats,
+    laser::{laser_cannon_bundle, LaserCannon, LaserPattern},
+    moving_cannon::{cannon_bundle, Cannon},
+    path::{FollowPath, PathMode, PathSegment},
+    ContactDamage, Enemy,
+};
+
+const ENEMIES_PATH: &str = "assets/enemies.ron";
+
+pub struct EnemyRegistryPlugin;
+
+impl Plugin for EnemyRegistryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_registry())
+            .add_systems(Startup, spawn_initial_enemies);
+    }
+}
+
+/// Every enemy type the game knows about, keyed by id.
+#[derive(Resource, Deserialize, Default)]
+#[serde(transparent)]
+pub struct EnemyRegistry {
+    pub definitions: HashMap<String, EnemyDefinition>,
+}
+
+impl EnemyRegistry {
+    pub fn get(&self, id: &str) -> Option<&EnemyDefinition> {
+        self.definitions.get(id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &String> {
+        self.definitions.keys()
+    }
+
+    /// Catches the definitions that load fine but can't be played.
+    pub fn validate(&self) -> Result<(), String> {
+        for (id, definition) in self.definitions.iter() {
+            let behaviours = std::iter::once(&definition.behaviour)
+                .chain(definition.boss_phases.iter().map(|phase| &phase.behaviour));
+            for behaviour in behaviours {
+                behaviour.validate().map_err(|e| format!("{}: {}", id, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct EnemyDefinition {
+    pub name: String,
+    pub sprite: String,
+    pub size: f32,
+    /// An sRGB tint for the sprite.
+    #[serde(default)]
+    pub color: Option<(u8, u8, u8)>,
+    pub health: f32,
+    #[serde(default = "default_factions")]
+    pub factions: Vec<FactionName>,
+    pub behaviour: EnemyBehaviour,
+    #[serde(default)]
+    pub movement: Option<MovementDefinition>,
+    /// Makes the enemy a boss, which goes through these phases in order.
+    #[serde(default)]
+    pub boss_phases: Vec<BossPhase>,
+}
+
+/// The serialized form of `UpgradesReceiverFaction`, which is a bitmask.
+#[derive(Clone, Copy, Deserialize)]
+pub enum FactionName {
+    Player,
+    Enemy,
+    EnemyBullets,
+}
+
+impl From<FactionName> for UpgradesReceiverFaction {
+    fn from(value: FactionName) -> Self {
+        match value {
+            FactionName::Player => UpgradesReceiverFaction::Player,
+            FactionName::Enemy => UpgradesReceiverFaction::Enemy,
+            FactionName::EnemyBullets => UpgradesReceiverFaction::EnemyBullets,
+        }
+    }
+}
+
+fn default_factions() -> Vec<FactionName> {
+    vec![FactionName::Enemy]
+}
+
+/// How the enemy attacks. Intervals are in seconds, and aim angles are relative to the spawn's `facing`.
+#[derive(Clone, Deserialize)]
+pub enum EnemyBehaviour {
+    Cannon {
+        bullet: BulletProperties,
+        shooting_interval: f32,
+    },
+    LaserCannon {
+        pattern: LaserPattern,
+        shooting_interval: f32,
+    },
+    Chaser {
+        steering: SteeringBehaviour,
+        max_speed: f32,
+        acceleration: f32,
+        contact_damage: f32,
+    },
+}
+
+impl EnemyBehaviour {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Chaser {
+                steering: SteeringBehaviour::Orbit { radius },
+                ..
+            } if *radius <= 0. => Err(format!("The orbit radius must be positive, not {}", radius)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Durations are in seconds.
+#[derive(Clone, Deserialize)]
+pub enum MovementDefinition {
+    /// Moves back and forth across the spawn position, perpendicular to `facing`.
+    Wander { length: f32, duration: f32 },
+    /// Points are offsets from the spawn position, in a frame where `facing` points down.
+    Path {
+        segments: Vec<PathSegment>,
+        #[serde(default)]
+        mode: PathMode,
+    },
+}
+
+impl MovementDefinition {
+    pub fn to_path(&self, position: Vec2, facing: Vec2) -> FollowPath {
+        match self {
+            Self::Wander { length, duration } => {
+                let offset = facing.normalize_or_zero().perp() * *length / 2.;
+                FollowPath::new(
+                    position - offset,
+                    vec![PathSegment::Line {
+                        to: position + offset,
+                        duration: *duration,
+                        ease: EaseFunction::Linear,
+                    }],
+                    PathMode::PingPong,
+                )
+            }
+            Self::Path { segments, mode } => {
+                let rotation = Rot2::radians(facing_angle(facing));
+                FollowPath::new(
+                    position,
+                    segments
+                        .iter()
+                        .map(|segment| segment.map_points(|point| position + rotation * point))
+                        .collect(),
+                    *mode,
+                )
+            }
+        }
+    }
+}
+
+/// The angle of `facing`, measured from straight down (like laser angles).
+fn facing_angle(facing: Vec2) -> f32 {
+    facing.x.atan2(-facing.y)
+}
+
+fn load_registry() -> EnemyRegistry {
+    let registry_string: String = fs::read_to_string(ENEMIES_PATH).unwrap();
+    ron::de::from_str::<EnemyRegistry>(&registry_string)
+        .map_err(|e| e.to_string())
+        .and_then(|registry| registry.validate().map(|_| registry))
+        .unwrap_or_else(|e| {
+            error!("Failed to load enemy definitions: {}", e);
+            std::process::exit(1);
+        })
+}
+
+/// How an enemy was spawned, so that the run can be saved with it and spawn it again.
+#[derive(Component, Clone)]
+pub struct EnemySpawn {
+    pub id: String,
+    pub position: Vec2,
+    pub facing: Vec2,
+}
+
+/// Spawns the enemy registered under `id`. `facing` is the direction it attacks in.
+pub struct SpawnEnemy {
+    pub id: String,
+    pub position: Vec2,
+    pub facing: Vec2,
+    pub spawning_animation: Option<Duration>,
+}
+
+impl SpawnEnemy {
+    pub fn new(id: impl Into<String>, position: Vec2, facing: Vec2) -> Self {
+        Self {
+            id: id.into(),
+            position,
+            facing,
+            spawning_animation: None,
+        }
+    }
+
+    pub fn with_spawning_animation(self, duration: Duration) -> Self {
+        Self {
+            spawning_animation: Some(duration),
+            ..self
+        }
+    }
+}
+
+impl Command for SpawnEnemy {
+    fn apply(self, world: &mut World) {
+        let Some(definition) = world.resource::<EnemyRegistry>().get(&self.id).cloned() else {
+            error!("Tried to spawn an unknown enemy: {}", self.id);
+            return;
+        };
+        let texture = world.resource::<AssetServer>().load(definition.sprite);
+        let factions = definition
+            .factions
+            .iter()
+            .fold(UpgradesReceiverFaction::none(), |factions, faction| {
+                factions | (*faction).into()
+            });
+
+        let path = definition
+            .movement
+            .map(|movement| movement.to_path(self.position, self.facing));
+        let position = path
+            .as_ref()
+            .map_or(self.position, |path| path.position_at(0.));
+
+        let mut entity = world.spawn((
+            Sprite {
+                custom_size: Some(Vec2::splat(definition.size)),
+                color: definition
+                    .color
+                    .map_or(Color::WHITE, |(r, g, b)| Color::srgb_u8(r, g, b)),
+                ..Sprite::from_image(texture)
+            },
+            Transform::from_translation(position.extend(game_z_index::CANNONS)),
+            Enemy,
+            Health::new(definition.health),
+            enemy_stats(),
+            UpgradesReceiver { factions },
+            Name::new(definition.name),
+            EnemySpawn {
+                id: self.id,
+                position: self.position,
+                facing: self.facing,
+            },
+        ));
+
+        insert_behaviour(
+            &mut entity,
+            definition.behaviour,
+            self.facing,
+            definition.size,
+        );
+
+        if let Some(path) = path {
+            entity.insert(path);
+        }
+
+        if !definition.boss_phases.is_empty() {
+            entity.insert(Boss::new(
+                definition.boss_phases,
+                self.position,
+                self.facing,
+                definition.size,
+            ));
+        }
+
+        if let Some(duration) = self.spawning_animation {
+            entity.insert(SpawningAnimation::new(duration));
+        }
+    }
+}
+
+pub(super) fn insert_behaviour(
+    entity: &mut EntityWorldMut,
+    behaviour: EnemyBehaviour,
+    facing: Vec2,
+    size: f32,
+) {
+    match behaviour {
+        EnemyBehaviour::Cannon {
+            bullet,
+            shooting_interval,
+        } => {
+            entity.insert((
+                cannon_bundle(
+                    bullet,
+                    facing.extend(0.),
+                    Duration::from_secs_f32(shooting_interval),
+                ),
+                enemy_collider(size),
+            ));
+        }
+        EnemyBehaviour::LaserCannon {
+            mut pattern,
+            shooting_interval,
+        } => {
+            pattern.aim += facing_angle(facing);
+            entity.insert((
+                laser_cannon_bundle(pattern, Duration::from_secs_f32(shooting_interval)),
+                enemy_collider(size),
+            ));
+        }
+        EnemyBehaviour::Chaser {
+            steering,
+            max_speed,
+            acceleration,
+            contact_damage,
+        } => {
+            entity.insert(chaser_bundle(
+                steering,
+                max_speed,
+                acceleration,
+                contact_damage,
+                size,
+            ));
+        }
+    }
+}
+
+/// Removes the components `insert_behaviour` adds, so that a different behaviour can be inserted.
+pub(super) fn remove_behaviour(entity: &mut EntityWorldMut) {
+    entity
+        .remove::<(Cannon, LaserCannon, Chaser, ContactDamage)>()
+        .remove::<(KinematicController, Sensor)>()
+        .insert(LinearVelocity::ZERO);
+}
+
+pub(super) fn spawn_initial_enemies(mut commands: Commands) {
+    commands.queue(SpawnEnemy::new("cannon", Vec2::new(-100., 0.), Vec2::X));
+    commands.queue(SpawnEnemy::new("cannon", Vec2::new(0., 70.), Vec2::NEG_Y));
+    commands.queue(SpawnEnemy::new(
+        "laser_cannon",
+        Vec2::new(0., 70.),
+        Vec2::NEG_Y,
+    ));
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::upgrades::{
+    GlobalUpgrade, Upgrade, UpgradeApplier, UpgradesPlugin, UpgradesReceiver,
+    UpgradesReceiverFaction,
+};
+
+use super::{
+    registry::{EnemyRegistry, SpawnEnemy},
+    Enemy, FireRateMultiplier,
+};
+
+const TEST_ENEMIES: &str = r#"{
+    "cannon": (
+        name: "Test cannon",
+        sprite: "character.png",
+        size: 7.5,
+        health: 10.,
+        behaviour: Cannon(
+            bullet: (damage: 5., size: 4., speed: 200.),
+            shooting_interval: 1.,
+        ),
+    ),
+    "laser_cannon": (
+        name: "Test laser cannon",
+        sprite: "character.png",
+        size: 7.5,
+        health: 10.,
+        behaviour: LaserCannon(
+            pattern: (aim: 0.),
+            shooting_interval: 1.,
+        ),
+    ),
+}"#;
+
+fn test_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), UpgradesPlugin))
+        .init_asset::<Image>()
+        .insert_resource(ron::de::from_str::<EnemyRegistry>(TEST_ENEMIES).unwrap());
+    app
+}
+
+fn spawn_enemy(world: &mut World, id: &str) -> Entity {
+    let mut q_enemies = world.query_filtered::<Entity, With<Enemy>>();
+    let before: Vec<Entity> = q_enemies.iter(world).collect();
+    SpawnEnemy::new(id, Vec2::ZERO, Vec2::NEG_Y).apply(world);
+    q_enemies
+        .iter(world)
+        .find(|entity| !before.contains(entity))
+        .unwrap()
+}
+
+fn double_fire_rate(In(entity): In<Entity>, mut q_fire_rate: Query<&mut FireRateMultiplier>) {
+    if let Ok(mut fire_rate) = q_fire_rate.get_mut(entity) {
+        fire_rate.0 *= 2.;
+    }
+}
+
+fn get_fire_rate_upgrade(world: &mut World) -> GlobalUpgrade {
+    GlobalUpgrade {
+        receiver_factions: UpgradesReceiverFaction::Enemy,
+        upgrade: Upgrade {
+            id: "test",
+            apply_upgrade: world.register_system(double_fire_rate),
+            description: "Test",
+            name: "Test",
+            icon_texture: Path::new(""),
+        },
+    }
+}
+
+fn fire_rate(world: &World, entity: Entity) -> f32 {
+    world.entity(entity).get::<FireRateMultiplier>().unwrap().0
+}
+
+#[test]
+fn test_enemies_are_upgrade_receivers() {
+    let mut app = test_app();
+    let world = app.world_mut();
+
+    for id in ["cannon", "laser_cannon"] {
+        let enemy = spawn_enemy(world, id);
+        let receiver = world.entity(enemy).get::<UpgradesReceiver>().unwrap();
+        assert!(receiver.factions.contains(UpgradesReceiverFaction::Enemy));
+        assert!(world.entity(enemy).get::<FireRateMultiplier>().is_some());
+    }
+}
+
+#[test]
+fn test_new_enemies_receive_applied_upgrades() {
+    let mut app = test_app();
+    let world = app.world_mut();
+    let old_cannon = spawn_enemy(world, "cannon");
+    app.update();
+
+    let world = app.world_mut();
+    let upgrade = get_fire_rate_upgrade(world);
+    let apply_upgrade = world.resource::<UpgradeApplier>().apply_upgrade_to_all;
+    world.run_system_with(apply_upgrade, upgrade).unwrap();
+    assert_eq!(fire_rate(world, old_cannon), 2.);
+
+    let new_cannon = spawn_enemy(world, "cannon");
+    let new_laser_cannon = spawn_enemy(world, "laser_cannon");
+    assert_eq!(fire_rate(world, new_cannon), 1.);
+    app.update();
+
+    // The new enemies get the upgrade when they spawn, and the old one doesn't get it again
+    let world = app.world();
+    assert_eq!(fire_rate(world, new_cannon), 2.);
+    assert_eq!(fire_rate(world, new_laser_cannon), 2.);
+    assert_eq!(fire_rate(world, old_cannon), 2.);
+}
+
+#[test]
+fn test_orbits_need_a_positive_radius() {
+    assert!(ron::de::from_str::<EnemyRegistry>(TEST_ENEMIES)
+        .unwrap()
+        .validate()
+        .is_ok());
+
+    let orbiter = |radius: f32| {
+        format!(
+            r#"{{
+    "orbiter": (
+        name: "Test orbiter",
+        sprite: "character.png",
+        size: 6.,
+        health: 10.,
+        behaviour: Chaser(
+            steering: Orbit(radius: {radius:?}),
+            max_speed: 40.,
+            acceleration: 80.,
+            contact_damage: 5.,
+        ),
+    ),
+}}"#
+        )
+    };
+    let registry = |radius| ron::de::from_str::<EnemyRegistry>(&orbiter(radius)).unwrap();
+    assert!(registry(30.).validate().is_ok());
+    assert!(registry(0.).validate().is_err());
+    assert!(registry(-5.).validate().is_err());
+}
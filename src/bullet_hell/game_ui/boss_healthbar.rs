@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::{
+    bullet_hell::{enemies::boss::Boss, health::Health},
+    ui,
+    utils::z_index,
+};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            spawn_boss_healthbar,
+            boss_healthbar_behaviour,
+            despawn_boss_healthbar,
+        ),
+    );
+}
+
+/// The root of a boss' healthbar at the top of the screen.
+#[derive(Component)]
+struct BossHealthbar {
+    boss: Entity,
+}
+
+#[derive(Component)]
+struct BossHealthbarFill {
+    boss: Entity,
+}
+
+fn spawn_boss_healthbar(q_bosses: Query<(Entity, &Name), Added<Boss>>, mut commands: Commands) {
+    for (boss, name) in q_bosses.iter() {
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                width: Val::Percent(50.),
+                left: Val::Percent(25.),
+                top: Val::Percent(3.),
+                ..default()
+            },
+            z_index::GAME_UI,
+            BossHealthbar { boss },
+            Name::new("Boss Healthbar"),
+            children![
+                (
+                    Text(name.as_str().into()),
+                    TextFont {
+                        font_size: 32.0,
+                        ..default()
+                    },
+                ),
+                (
+                    Node {
+                        width: Val::Percent(100.),
+                        height: Val::Px(30.),
+                        border: UiRect::all(Val::Px(5.)),
+                        justify_content: JustifyContent::Start,
+                        ..default()
+                    },
+                    BorderColor(ui::palette::BLACK),
+                    BackgroundColor(ui::palette::DARK_GRAY),
+                    children![(
+                        Node {
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100.),
+                            ..default()
+                        },
+                        BackgroundColor(ui::palette::RED),
+                        BossHealthbarFill { boss },
+                    )],
+                ),
+            ],
+        ));
+    }
+}
+
+fn boss_healthbar_behaviour(
+    q_health: Query<&Health, With<Boss>>,
+    mut q_fills: Query<(&mut Node, &BossHealthbarFill)>,
+) {
+    for (mut node, fill) in q_fills.iter_mut() {
+        let Ok(health) = q_health.get(fill.boss) else {
+            continue;
+        };
+        node.width = Val::Percent(100. * (health.health / health.max_health).max(0.));
+    }
+}
+
+fn despawn_boss_healthbar(
+    q_healthbars: Query<(Entity, &BossHealthbar)>,
+    q_bosses: Query<(), With<Boss>>,
+    mut commands: Commands,
+) {
+    for (entity, healthbar) in q_healthbars.iter() {
+        if !q_bosses.contains(healthbar.boss) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 
 use crate::{
-    bullet_hell::health::Health,
+    bullet_hell::health::{HealOverTime, Health, Overheal, Shield},
     ui,
     utils::{world_ui::WorldUI, z_index},
     AppState,
@@ -23,8 +23,60 @@ struct Healthbar {
     tracked_entity: Entity,
 }
 
-#[derive(Component)]
-struct GreenPart;
+#[derive(Component, Clone, Copy)]
+enum HealthbarPart {
+    Health,
+    /// The healing that's still on its way, right after the health.
+    IncomingHeal,
+    /// A strip over the top of the healthbar.
+    Shield,
+    /// A strip over the bottom of the healthbar.
+    Overheal,
+}
+
+impl HealthbarPart {
+    /// How much of the healthbar the part fills, from 0 to 1.
+    fn fraction(
+        &self,
+        health: &Health,
+        shield: Option<&Shield>,
+        heal_over_time: Option<&HealOverTime>,
+        overheal: Option<&Overheal>,
+    ) -> f32 {
+        let fraction = match self {
+            Self::Health => health.health / health.max_health,
+            Self::IncomingHeal => heal_over_time.map_or(0., |heal| {
+                heal.remaining.min(health.max_health - health.health) / health.max_health
+            }),
+            Self::Shield => shield.map_or(0., |shield| shield.points / shield.max_points),
+            Self::Overheal => overheal.map_or(0., |overheal| overheal.points / health.max_health),
+        };
+        fraction.clamp(0., 1.)
+    }
+}
+
+fn strip(part: HealthbarPart, color: Color, character_entity: Entity) -> impl Bundle {
+    let (top, bottom) = match part {
+        HealthbarPart::Shield => (Val::Px(0.), Val::Auto),
+        _ => (Val::Auto, Val::Px(0.)),
+    };
+    (
+        Node {
+            width: Val::Percent(0.),
+            height: Val::Percent(30.),
+            position_type: PositionType::Absolute,
+            top,
+            bottom,
+            left: Val::Px(0.),
+            ..default()
+        },
+        BackgroundColor(color),
+        part,
+        Healthbar {
+            tracked_entity: character_entity,
+        },
+    )
+}
 
 pub fn spawn_healthbar(commands: &mut Commands, character_entity: Entity) {
     commands.spawn((
@@ -50,28 +102,50 @@ pub fn spawn_healthbar(commands: &mut Commands, character_entity: Entity) {
             },
             BorderColor(ui::palette::BLACK),
             Name::new("Healthbar"),
-            children![(
-                Node {
-                    width: Val::Percent(100.),
-                    height: Val::Percent(100.),
-                    ..default()
-                },
-                BackgroundColor(ui::palette::GREEN),
-                GreenPart,
-                Healthbar {
-                    tracked_entity: character_entity,
-                },
-            )],
+            children![
+                (
+                    Node {
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    BackgroundColor(ui::palette::GREEN),
+                    HealthbarPart::Health,
+                    Healthbar {
+                        tracked_entity: character_entity,
+                    },
+                ),
+                (
+                    Node {
+                        width: Val::Percent(0.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    BackgroundColor(ui::palette::GREEN.with_alpha(0.4)),
+                    HealthbarPart::IncomingHeal,
+                    Healthbar {
+                        tracked_entity: character_entity,
+                    },
+                ),
+                strip(HealthbarPart::Shield, ui::palette::BLUE, character_entity),
+                strip(HealthbarPart::Overheal, ui::palette::GOLD, character_entity),
+            ],
         )],
     ));
 }
 
 fn healthbar_behaviour(
-    health_query: Query<&Health>,
-    mut healthbar_query: Query<(&mut Node, &Healthbar), With<GreenPart>>,
+    health_query: Query<(
+        &Health,
+        Option<&Shield>,
+        Option<&HealOverTime>,
+        Option<&Overheal>,
+    )>,
+    mut healthbar_query: Query<(&mut Node, &Healthbar, &HealthbarPart)>,
 ) {
-    for (mut node, healthbar) in healthbar_query.iter_mut() {
-        let health_component = health_query.get(healthbar.tracked_entity).unwrap();
-        node.width = Val::Percent(100. * health_component.health / health_component.max_health);
+    for (mut node, healthbar, part) in healthbar_query.iter_mut() {
+        let (health, shield, heal_over_time, overheal) =
+            health_query.get(healthbar.tracked_entity).unwrap();
+        node.width = Val::Percent(100. * part.fraction(health, shield, heal_over_time, overheal));
     }
 }
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use crate::{bullet_hell::items::Inventory, ui, utils::z_index};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(PostStartup, spawn_item_bar)
+        .add_systems(Update, render_items.run_if(resource_changed::<Inventory>));
+}
+
+#[derive(Component)]
+struct ItemBar;
+
+fn spawn_item_bar(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(10.),
+            bottom: Val::Percent(20.),
+            column_gap: Val::Px(10.),
+            ..default()
+        },
+        z_index::GAME_UI,
+        ItemBar,
+        Name::new("Item Bar"),
+    ));
+}
+
+/// One icon per item, numbered by the key that uses it.
+fn render_items(
+    inventory: Res<Inventory>,
+    q_bar: Query<Entity, With<ItemBar>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    for bar in q_bar.iter() {
+        commands
+            .entity(bar)
+            .despawn_related::<Children>()
+            .with_children(|builder| {
+                for (slot, item) in inventory.items().iter().enumerate() {
+                    builder.spawn((
+                        Node {
+                            width: Val::Px(48.),
+                            height: Val::Px(48.),
+                            ..default()
+                        },
+                        ImageNode::new(asset_server.load(item.icon_texture())),
+                        BackgroundColor(ui::palette::WHITE),
+                        Name::new(item.name()),
+                        children![(
+                            Text(format!("{}", slot + 1)),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(ui::palette::BLACK),
+                        )],
+                    ));
+                }
+            });
+    }
+}
@@ -3,12 +3,19 @@ use bevy::prelude::*;
 use self::healthbar::HealthbarPlugin;
 
 pub mod abilities;
+pub mod boss_healthbar;
 pub mod healthbar;
+pub mod items;
 
 pub struct GameUIPlugin;
 
 impl Plugin for GameUIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((abilities::plugin, HealthbarPlugin));
+        app.add_plugins((
+            abilities::plugin,
+            boss_healthbar::plugin,
+            HealthbarPlugin,
+            items::plugin,
+        ));
     }
 }
@@ -1,3 +1,6 @@
+pub const GOAL_ZONES: f32 = -5.;
+pub const PICKUPS: f32 = -4.75;
+pub const AFTERIMAGES: f32 = -4.5;
 pub const PLAYERS: f32 = -4.;
 pub const CANNONS: f32 = -3.;
 pub const LASERS: f32 = -2.;
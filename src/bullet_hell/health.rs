@@ -1,21 +1,43 @@
-use std::time::Duration;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
-use bevy::prelude::*;
+use bevy::{ecs::query::QueryData, prelude::*};
 use bevy_inspector_egui::prelude::ReflectInspectorOptions;
 use bevy_inspector_egui::InspectorOptions;
 
-use crate::AppState;
+use crate::{AppState, DefendingState, RewindRunEvent};
 
 pub struct HealthPlugin;
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<TryDamageEvent>()
             .add_event::<DamageOccurredEvent>()
+            .add_event::<HealEvent>()
             .register_type::<Health>()
+            .register_type::<HitInvulnerability>()
+            .register_type::<Shield>()
+            .register_type::<Armor>()
+            .register_type::<Regeneration>()
+            .register_type::<HealOverTime>()
+            .register_type::<Overheal>()
+            // Nothing lands behind the pause menu or on the dying player. The map's events can hurt
+            // too, and the hit's invulnerability wears off there
             .add_systems(
                 Update,
-                (handle_damage, handle_invulnerability).run_if(in_state(AppState::Defending)),
-            );
+                (handle_damage, handle_invulnerability)
+                    .run_if(in_state(DefendingState::Fighting).or(in_state(AppState::ActionMenu))),
+            )
+            .add_systems(
+                Update,
+                (regenerate_shields, regenerate_health, decay_overheal)
+                    .run_if(in_state(AppState::Defending)),
+            )
+            // Heals can come from the map and the shops too, and they should land before anything
+            // that happens on the next state change, like saving the run
+            .add_systems(PostUpdate, handle_healing)
+            .add_observer(remove_invulnerability);
     }
 }
 
@@ -36,36 +58,324 @@ impl Health {
     }
 }
 
+/// How long an entity can't get hurt again after getting hit. Entities without it get a second.
+#[derive(Component, InspectorOptions, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct HitInvulnerability {
+    pub duration: Duration,
+}
+
+impl Default for HitInvulnerability {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Absorbs damage before it gets to the health, and regenerates after a while without getting hit.
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct Shield {
+    pub points: f32,
+    pub max_points: f32,
+    /// Points per second.
+    pub regeneration: f32,
+    pub regeneration_delay: Duration,
+    since_hit: Duration,
+}
+
+impl Shield {
+    pub fn new(max_points: f32, regeneration: f32, regeneration_delay: Duration) -> Self {
+        Self {
+            points: max_points,
+            max_points,
+            regeneration,
+            regeneration_delay,
+            since_hit: Duration::ZERO,
+        }
+    }
+
+    /// Returns the damage that got through.
+    fn absorb(&mut self, damage: f32) -> f32 {
+        let absorbed = damage.min(self.points);
+        self.points -= absorbed;
+        self.since_hit = Duration::ZERO;
+        damage - absorbed
+    }
+}
+
+/// Reduces every hit by `percent` (from 0 to 1), and then by `flat`.
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct Armor {
+    pub flat: f32,
+    pub percent: f32,
+}
+
+impl Armor {
+    fn mitigate(&self, damage: f32) -> f32 {
+        (damage * (1. - self.percent.clamp(0., 1.)) - self.flat).max(0.)
+    }
+}
+
+/// Multiplies the damage of specific types, before the armor. 0 means immune to the type.
+#[derive(Component, Default)]
+pub struct Resistances {
+    multipliers: HashMap<DamageType, f32>,
+}
+
+impl Resistances {
+    pub fn multiply(&mut self, damage_type: DamageType, multiplier: f32) {
+        *self.multipliers.entry(damage_type).or_insert(1.) *= multiplier;
+    }
+
+    fn multiplier(&self, damage_type: DamageType) -> f32 {
+        self.multipliers.get(&damage_type).copied().unwrap_or(1.)
+    }
+}
+
+/// The reasons the entity can't get hurt right now, each added and removed by whoever it belongs
+/// to. Unlike `Invulnerability`, they don't cut each other short.
+#[derive(Component, Default)]
+pub struct DamageImmunity {
+    tags: HashSet<&'static str>,
+}
+
+impl DamageImmunity {
+    pub fn add(entity: &mut EntityCommands, tag: &'static str) {
+        entity
+            .entry::<DamageImmunity>()
+            .or_default()
+            .and_modify(move |mut immunity| {
+                immunity.tags.insert(tag);
+            });
+    }
+
+    pub fn remove(entity: &mut EntityCommands, tag: &'static str) {
+        entity
+            .entry::<DamageImmunity>()
+            .and_modify(move |mut immunity| {
+                immunity.tags.remove(tag);
+            });
+    }
+
+    fn is_immune(&self) -> bool {
+        !self.tags.is_empty()
+    }
+}
+
+/// What dealt the damage, for whoever cares how the entity got hurt.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DamageType {
+    Bullet,
+    Laser,
+    /// Running into an enemy.
+    Contact,
+    Sword,
+    Dash,
+    /// Like the debug tools.
+    Other,
+}
+
+impl DamageType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DamageType::Bullet => "Bullet",
+            DamageType::Laser => "Laser",
+            DamageType::Contact => "Contact",
+            DamageType::Sword => "Sword",
+            DamageType::Dash => "Dash",
+            DamageType::Other => "Other",
+        }
+    }
+}
+
+/// Heals every second, for as long as the entity has it.
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct Regeneration {
+    pub per_second: f32,
+}
+
+/// Heals `remaining` more HP over time, and goes away once it's done.
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct HealOverTime {
+    pub remaining: f32,
+    pub per_second: f32,
+}
+
+/// Lets healing go up to `max_points` over the max health. The extra points decay over time,
+/// and get lost before the health does.
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct Overheal {
+    pub points: f32,
+    pub max_points: f32,
+    /// Points per second.
+    pub decay: f32,
+}
+
+impl Overheal {
+    pub fn new(max_points: f32, decay: f32) -> Self {
+        Self {
+            points: 0.,
+            max_points,
+            decay,
+        }
+    }
+
+    /// Returns how much of the damage the overheal took.
+    fn drain(&mut self, damage: f32) -> f32 {
+        let drained = damage.min(self.points);
+        self.points -= drained;
+        drained
+    }
+}
+
 #[derive(Event)]
 pub struct TryDamageEvent {
     pub target_entity: Entity,
     pub damage: f32,
+    pub damage_type: DamageType,
+    /// Whoever dealt the damage (for bullets, whoever shot them), if anyone.
+    pub source: Option<Entity>,
 }
 
+/// Sent for every hit that landed, even if nothing got through the armor and shield.
 #[derive(Event)]
 pub struct DamageOccurredEvent {
     pub target_entity: Entity,
-    #[allow(dead_code)]
+    /// What the health actually lost, after the armor and shield.
     pub damage: f32,
+    /// What the shield took instead.
+    pub absorbed: f32,
+    pub damage_type: DamageType,
+    pub source: Option<Entity>,
+}
+
+/// Heals the target up to its max health, and puts the rest in its `Overheal` if it has one.
+#[derive(Event)]
+pub struct HealEvent {
+    pub target_entity: Entity,
+    pub amount: f32,
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct DamageTarget {
+    entity: Entity,
+    health: &'static mut Health,
+    shield: Option<&'static mut Shield>,
+    overheal: Option<&'static mut Overheal>,
+    resistances: Option<&'static Resistances>,
+    armor: Option<&'static Armor>,
+    immunity: Option<&'static DamageImmunity>,
+    has_invulnerability: Has<Invulnerability>,
+    hit_invulnerability: Option<&'static HitInvulnerability>,
 }
 
-fn handle_damage(
+pub(super) fn handle_damage(
     mut reader: EventReader<TryDamageEvent>,
     mut damage_occurred_writer: EventWriter<DamageOccurredEvent>,
-    mut query: Query<(Entity, &mut Health, Has<Invulnerability>)>,
+    mut query: Query<DamageTarget>,
     mut commands: Commands,
 ) {
     for event in reader.read() {
-        let (entity, mut health, has_invulnerability) = query.get_mut(event.target_entity).unwrap();
-        if !has_invulnerability {
-            damage_occurred_writer.write(DamageOccurredEvent {
-                target_entity: event.target_entity,
-                damage: event.damage,
-            });
-            health.health -= event.damage;
-            commands
-                .entity(entity)
-                .insert(Invulnerability::new(Duration::from_secs(1)));
+        // The target might be gone, like an enemy that died from an earlier hit
+        let Ok(mut target) = query.get_mut(event.target_entity) else {
+            continue;
+        };
+        if target.has_invulnerability || target.immunity.is_some_and(|i| i.is_immune()) {
+            continue;
+        }
+        let resisted = target.resistances.map_or(event.damage, |resistances| {
+            event.damage * resistances.multiplier(event.damage_type)
+        });
+        let mitigated = target
+            .armor
+            .map_or(resisted, |armor| armor.mitigate(resisted));
+        let damage = match target.shield.as_mut() {
+            Some(shield) => shield.absorb(mitigated),
+            None => mitigated,
+        };
+        damage_occurred_writer.write(DamageOccurredEvent {
+            target_entity: event.target_entity,
+            damage,
+            absorbed: mitigated - damage,
+            damage_type: event.damage_type,
+            source: event.source,
+        });
+        let from_overheal = target
+            .overheal
+            .as_mut()
+            .map_or(0., |overheal| overheal.drain(damage));
+        target.health.health -= damage - from_overheal;
+        let duration = target
+            .hit_invulnerability
+            .map_or(HitInvulnerability::default().duration, |hit| hit.duration);
+        commands
+            .entity(target.entity)
+            .insert(Invulnerability::new(duration));
+    }
+}
+
+fn handle_healing(
+    mut reader: EventReader<HealEvent>,
+    mut query: Query<(&mut Health, Option<&mut Overheal>)>,
+) {
+    for event in reader.read() {
+        // The target might be gone, like an enemy that died while regenerating
+        let Ok((mut health, overheal)) = query.get_mut(event.target_entity) else {
+            continue;
+        };
+        let healed = event.amount.min(health.max_health - health.health).max(0.);
+        health.health += healed;
+        if let Some(mut overheal) = overheal {
+            overheal.points = (overheal.points + event.amount - healed).min(overheal.max_points);
+        }
+    }
+}
+
+fn regenerate_health(
+    q_regeneration: Query<(Entity, &Regeneration)>,
+    mut q_heal_over_time: Query<(Entity, &mut HealOverTime)>,
+    mut heal_writer: EventWriter<HealEvent>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, regeneration) in q_regeneration.iter() {
+        heal_writer.write(HealEvent {
+            target_entity: entity,
+            amount: regeneration.per_second * time.delta_secs(),
+        });
+    }
+    for (entity, mut heal_over_time) in q_heal_over_time.iter_mut() {
+        let amount = (heal_over_time.per_second * time.delta_secs()).min(heal_over_time.remaining);
+        heal_over_time.remaining -= amount;
+        heal_writer.write(HealEvent {
+            target_entity: entity,
+            amount,
+        });
+        if heal_over_time.remaining <= 0. {
+            commands.entity(entity).remove::<HealOverTime>();
+        }
+    }
+}
+
+fn decay_overheal(mut query: Query<&mut Overheal>, time: Res<Time>) {
+    for mut overheal in query.iter_mut() {
+        overheal.points = (overheal.points - overheal.decay * time.delta_secs()).max(0.);
+    }
+}
+
+fn regenerate_shields(mut query: Query<&mut Shield>, time: Res<Time>) {
+    for mut shield in query.iter_mut() {
+        shield.since_hit += time.delta();
+        if shield.since_hit >= shield.regeneration_delay {
+            shield.points =
+                (shield.points + shield.regeneration * time.delta_secs()).min(shield.max_points);
         }
     }
 }
@@ -84,6 +394,18 @@ impl Invulnerability {
     }
 }
 
+fn remove_invulnerability(
+    _trigger: Trigger<RewindRunEvent>,
+    query: Query<Entity, Or<(With<Invulnerability>, With<DamageImmunity>)>>,
+    mut commands: Commands,
+) {
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .remove::<(Invulnerability, DamageImmunity)>();
+    }
+}
+
 fn handle_invulnerability(
     mut query: Query<(Entity, &mut Invulnerability)>,
     time: Res<Time>,
@@ -96,3 +418,32 @@ fn handle_invulnerability(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_armor_and_shield_mitigation() {
+        let armor = Armor {
+            flat: 1.,
+            percent: 0.5,
+        };
+        assert_eq!(armor.mitigate(10.), 4.);
+        assert_eq!(armor.mitigate(1.), 0.);
+
+        let mut shield = Shield::new(5., 1., Duration::from_secs(3));
+        assert_eq!(shield.absorb(3.), 0.);
+        assert_eq!(shield.absorb(4.), 2.);
+        assert_eq!(shield.points, 0.);
+    }
+
+    #[test]
+    fn test_overheal_is_lost_first() {
+        let mut overheal = Overheal::new(10., 1.);
+        overheal.points = 3.;
+        assert_eq!(overheal.drain(2.), 2.);
+        assert_eq!(overheal.drain(5.), 1.);
+        assert_eq!(overheal.points, 0.);
+    }
+}
@@ -5,7 +5,7 @@ use bevy_tweening::*;
 
 use crate::utils::animation::lenses::ColorMaterialRGBLens;
 
-use super::health::{DamageOccurredEvent, Invulnerability};
+use super::health::{DamageOccurredEvent, DamageType, Invulnerability};
 
 pub struct HitEffectPlugin;
 
@@ -22,6 +22,19 @@ impl Plugin for HitEffectPlugin {
     }
 }
 
+fn flash_color(event: &DamageOccurredEvent) -> Color {
+    use bevy::color::palettes::css;
+    // Hits that didn't get through the shield
+    if event.damage == 0. && event.absorbed > 0. {
+        return css::DEEP_SKY_BLUE.into();
+    }
+    match event.damage_type {
+        DamageType::Laser => css::ORANGE.into(),
+        DamageType::Sword | DamageType::Dash => css::WHITE.into(),
+        DamageType::Bullet | DamageType::Contact | DamageType::Other => css::RED.into(),
+    }
+}
+
 // TODO: This animation happens even when there's the invul component
 fn flash_on_damage(
     mut reader: EventReader<DamageOccurredEvent>,
@@ -34,7 +47,9 @@ fn flash_on_damage(
     // fades out I want to reveal an already opacitied-out player.
 
     for event in reader.read() {
-        let color_handle = color_query.get_mut(event.target_entity).unwrap();
+        let Ok(color_handle) = color_query.get_mut(event.target_entity) else {
+            continue;
+        };
         let material = materials.get(color_handle).unwrap();
         let current_color = material.color;
         let tween = Tween::new(
@@ -43,7 +58,7 @@ fn flash_on_damage(
             // I'm using this and not the standard ColorLens to allow
             // messing with the alpha in the invulnerability systems.
             ColorMaterialRGBLens {
-                start: bevy::color::palettes::basic::RED.into(),
+                start: flash_color(event),
                 end: current_color,
             },
         );
@@ -72,7 +87,9 @@ fn remove_invulnerability_effect(
     query: Query<&MeshMaterial2d<ColorMaterial>>,
 ) {
     for entity in removed_components.read() {
-        let color_handle = query.get(entity).unwrap();
+        let Ok(color_handle) = query.get(entity) else {
+            continue;
+        };
         materials.get_mut(color_handle).unwrap().color.set_alpha(1.);
     }
 }
@@ -0,0 +1,183 @@
+use std::{path::Path, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{DefendingState, RewindRunEvent};
+
+use super::{
+    bullet::{Bullet, PlayerBullet},
+    health::{HealEvent, Invulnerability},
+    player::Player,
+};
+
+const HEAL_AMOUNT: f32 = 10.;
+const SHIELD_DURATION: Duration = Duration::from_secs(5);
+
+/// The keys that use the items in the inventory's slots, in order.
+const ITEM_HOTKEYS: [KeyCode; Inventory::MAX_ITEMS] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+];
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Inventory>()
+        .add_event::<UseItemEvent>()
+        .add_observer(reset_inventory)
+        .add_systems(
+            Update,
+            (
+                use_items_with_hotkeys.run_if(in_state(DefendingState::Fighting)),
+                use_items,
+            )
+                .chain(),
+        );
+}
+
+/// Single-use items, kept in the `Inventory` until they're used.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Item {
+    Heal,
+    /// Can't get hurt for a while.
+    Shield,
+    /// Clears the enemies' bullets.
+    Bomb,
+}
+
+impl Item {
+    pub const ALL: [Item; 3] = [Item::Heal, Item::Shield, Item::Bomb];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Item::Heal => "Potion",
+            Item::Shield => "Shield",
+            Item::Bomb => "Bomb",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Item::Heal => "Heal 10 HP",
+            Item::Shield => "Can't get hurt for 5 seconds",
+            Item::Bomb => "Clear all enemy bullets",
+        }
+    }
+
+    pub fn icon_texture(&self) -> &'static Path {
+        match self {
+            Item::Heal => Path::new("sprites/upgrades/apple.png"),
+            Item::Shield => Path::new("sprites/upgrades/sword_diamond.png"),
+            Item::Bomb => Path::new("sprites/upgrades/yellow.png"),
+        }
+    }
+
+    /// Whether the item does anything outside of a fight. Bombs would only go to waste.
+    pub fn usable_outside_fights(&self) -> bool {
+        !matches!(self, Item::Bomb)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Inventory {
+    items: Vec<Item>,
+}
+
+impl Inventory {
+    pub const MAX_ITEMS: usize = 4;
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// Returns whether there was room for the item.
+    pub fn add(&mut self, item: Item) -> bool {
+        if self.items.len() >= Self::MAX_ITEMS {
+            return false;
+        }
+        self.items.push(item);
+        true
+    }
+
+    fn take(&mut self, slot: usize) -> Option<Item> {
+        (slot < self.items.len()).then(|| self.items.remove(slot))
+    }
+}
+
+fn reset_inventory(_trigger: Trigger<RewindRunEvent>, mut inventory: ResMut<Inventory>) {
+    inventory.items.clear();
+}
+
+/// Uses the item in the given slot of the inventory on the player.
+#[derive(Event)]
+pub struct UseItemEvent {
+    pub slot: usize,
+}
+
+fn use_items_with_hotkeys(
+    input: Res<ButtonInput<KeyCode>>,
+    mut use_item_writer: EventWriter<UseItemEvent>,
+) {
+    for (slot, key) in ITEM_HOTKEYS.iter().enumerate() {
+        if input.just_pressed(*key) {
+            use_item_writer.write(UseItemEvent { slot });
+        }
+    }
+}
+
+fn use_items(
+    mut use_item_events: EventReader<UseItemEvent>,
+    mut inventory: ResMut<Inventory>,
+    q_player: Query<Entity, With<Player>>,
+    q_bullets: Query<Entity, (With<Bullet>, Without<PlayerBullet>)>,
+    mut heal_writer: EventWriter<HealEvent>,
+    mut commands: Commands,
+) {
+    for event in use_item_events.read() {
+        let Some(item) = inventory.take(event.slot) else {
+            continue;
+        };
+        match item {
+            Item::Heal => {
+                for player in q_player.iter() {
+                    heal_writer.write(HealEvent {
+                        target_entity: player,
+                        amount: HEAL_AMOUNT,
+                    });
+                }
+            }
+            Item::Shield => {
+                for player in q_player.iter() {
+                    commands
+                        .entity(player)
+                        .insert(Invulnerability::new(SHIELD_DURATION));
+                }
+            }
+            Item::Bomb => {
+                for bullet in q_bullets.iter() {
+                    commands.entity(bullet).try_despawn();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inventory_is_limited() {
+        let mut inventory = Inventory::default();
+        for _ in 0..Inventory::MAX_ITEMS {
+            assert!(inventory.add(Item::Heal));
+        }
+        assert!(!inventory.add(Item::Bomb));
+
+        assert_eq!(inventory.take(0), Some(Item::Heal));
+        assert_eq!(inventory.take(Inventory::MAX_ITEMS), None);
+        assert!(inventory.add(Item::Bomb));
+        assert_eq!(inventory.items().last(), Some(&Item::Bomb));
+    }
+}
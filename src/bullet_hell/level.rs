@@ -4,7 +4,10 @@ use bevy::prelude::*;
 
 use crate::AppState;
 
-use super::level_end_animation::AnimationFinishedEvent;
+use super::{
+    level_end_animation::AnimationFinishedEvent,
+    objectives::{LoseCondition, WinCondition},
+};
 pub struct LevelPlugin;
 
 #[derive(Event)]
@@ -14,15 +17,37 @@ pub struct LevelFinishedEvent;
 // TODO: Should this be Clone?
 #[derive(Default, Clone)]
 pub struct LevelConfig {
-    pub duration: Duration,
+    pub win: WinCondition,
+    /// The level is lost as soon as any of these is met, even if the player is still alive.
+    pub lose: &'static [LoseCondition],
+    /// The id of a boss that spawns when the level starts.
+    pub boss: Option<&'static str>,
 }
 
 impl LevelConfig {
+    pub const fn new(win: WinCondition) -> Self {
+        Self {
+            win,
+            lose: &[],
+            boss: None,
+        }
+    }
+
     pub const fn from_seconds_duration(seconds: u64) -> Self {
+        Self::new(WinCondition::Survive(Duration::from_secs(seconds)))
+    }
+
+    pub const fn boss_fight(boss: &'static str) -> Self {
         Self {
-            duration: Duration::from_secs(seconds),
+            win: WinCondition::DefeatBoss,
+            lose: &[],
+            boss: Some(boss),
         }
     }
+
+    pub const fn with_lose_conditions(self, lose: &'static [LoseCondition]) -> Self {
+        Self { lose, ..self }
+    }
 }
 
 #[derive(Resource, Default)]
@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 
-use crate::AppState;
+use crate::{game_config::GameConfig, utils::resources::RunRng, AppState};
 
-use super::effects::{effect::LevelTransitionEffectsPool, spawning_animation::SpawningAnimation};
+use super::{
+    difficulty::{DifficultyCurve, DifficultyLevel},
+    effects::{effect::LevelTransitionEffectsPool, spawning_animation::SpawningAnimation},
+};
 
 pub struct LevelEndAnimationPlugin;
 
@@ -54,8 +57,17 @@ fn reset_animation_state(mut animation_state: ResMut<NextState<LevelEndAnimation
     animation_state.set(LevelEndAnimationState::Animations);
 }
 
-fn apply_random_effect(effects: Res<LevelTransitionEffectsPool>, mut commands: Commands) {
-    commands.run_system(effects.get_random().0);
+fn apply_random_effect(
+    effects: Res<LevelTransitionEffectsPool>,
+    config: Res<GameConfig>,
+    level: Res<DifficultyLevel>,
+    mut run_rng: ResMut<RunRng>,
+    mut commands: Commands,
+) {
+    let count = DifficultyCurve::new(config.difficulty).enemies_after_level(level.0);
+    for _ in 0..count {
+        commands.run_system(effects.get_random(run_rng.rng()).0);
+    }
 }
 
 fn check_for_animations_end(
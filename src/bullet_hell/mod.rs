@@ -1,7 +1,19 @@
 use bevy::prelude::*;
 
+pub use self::dash::DashStartedEvent;
+pub use self::death::ReviveUsed;
+pub use self::difficulty::{DifficultyLevel, ScaleDifficulty};
+pub use self::enemies::{
+    boss::Boss,
+    registry::{EnemySpawn, SpawnEnemy},
+    Enemy,
+};
+pub use self::health::{DamageOccurredEvent, DamageType, HealEvent, Health, TryDamageEvent};
+pub use self::items::{Inventory, Item, UseItemEvent};
 pub use self::level::{CurrentLevelConfig, LevelConfig, LevelFinishedEvent};
-pub use abilities3::AbilityUpgradePool;
+pub use self::objectives::{LoseCondition, WinCondition};
+pub use self::player::Player;
+pub use abilities3::{AbilityUpgradePool, ParryEvent, ParryKind};
 
 mod abilities3;
 mod effects;
@@ -12,15 +24,20 @@ mod physics_layers;
 mod arena;
 mod bullet;
 mod dash;
+mod death;
 mod debug;
+mod difficulty;
 mod game_z_index;
 mod health;
 mod hit_effect;
+mod items;
 mod level;
 mod level_end_animation;
-mod level_timer;
+mod objectives;
+mod pickups;
 mod player;
 mod sword;
+pub mod upgrade_hooks;
 mod upgrades;
 
 pub struct BulletHellPlugin;
@@ -33,8 +50,11 @@ impl Plugin for BulletHellPlugin {
                 abilities3::plugin,
                 bullet::BulletsPlugin,
                 dash::DashPlugin,
+                death::plugin,
                 debug::DebugPlugin,
+                difficulty::plugin,
                 effects::EffectsPlugin,
+                enemies::EnemyPlugin,
                 enemies::laser::LaserPlugin,
                 enemies::moving_cannon::MovingCannonPlugin,
                 game_ui::GameUIPlugin,
@@ -42,11 +62,14 @@ impl Plugin for BulletHellPlugin {
             (
                 health::HealthPlugin,
                 hit_effect::HitEffectPlugin,
+                items::plugin,
                 level::LevelPlugin,
                 level_end_animation::LevelEndAnimationPlugin,
-                level_timer::LevelTimerPlugin,
+                objectives::ObjectivesPlugin,
+                pickups::plugin,
                 player::PlayerPlugin,
                 sword::SwordPlugin,
+                upgrade_hooks::plugin,
             ),
         ))
         .add_systems(Startup, upgrades::populate_upgrades_pool); // TODO: The upgrade pool and ability pool are referenced in different ways
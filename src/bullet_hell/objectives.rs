@@ -0,0 +1,294 @@
+use std::time::Duration;
+
+use bevy::{math::vec3, prelude::*};
+
+use crate::{
+    ui::{self, lose_screen::LoseEvent},
+    utils::{world_ui::WorldUI, z_index},
+    AppState,
+};
+
+use super::{
+    abilities3::{ParryEvent, ParryKind},
+    enemies::{boss::Boss, Enemy},
+    game_z_index,
+    health::DamageOccurredEvent,
+    level::{CombatFinishedEvent, CurrentLevelConfig},
+    player::Player,
+};
+
+pub struct ObjectivesPlugin;
+
+impl Plugin for ObjectivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelProgress>()
+            .add_systems(Startup, spawn_objective_display)
+            .add_systems(
+                OnEnter(AppState::Defending),
+                (reset_progress, spawn_goal_zones),
+            )
+            .add_systems(OnExit(AppState::Defending), despawn_goal_zones)
+            .add_systems(
+                Update,
+                (track_progress, check_conditions, update_objective_display)
+                    .chain()
+                    .run_if(in_state(AppState::Defending)),
+            );
+    }
+}
+
+/// What the player has to do to finish a level.
+#[derive(Clone)]
+pub enum WinCondition {
+    Survive(Duration),
+    DefeatAllEnemies,
+    DefeatBoss,
+    /// Touch the zone at some point during the level.
+    ReachGoal {
+        position: Vec2,
+        radius: f32,
+    },
+    /// Parry this many bullets (blocking lasers doesn't count).
+    Parry(u32),
+    AllOf(&'static [WinCondition]),
+    AnyOf(&'static [WinCondition]),
+}
+
+impl Default for WinCondition {
+    fn default() -> Self {
+        Self::Survive(Duration::from_secs(10))
+    }
+}
+
+impl WinCondition {
+    fn is_met(&self, progress: &LevelProgress) -> bool {
+        match self {
+            Self::Survive(duration) => progress.elapsed >= *duration,
+            Self::DefeatAllEnemies => progress.enemies_remaining == 0,
+            // A boss that never showed up (like one with an unknown id) wasn't defeated
+            Self::DefeatBoss => progress.boss_appeared && progress.bosses_remaining == 0,
+            Self::ReachGoal { .. } => progress.reached_goal,
+            Self::Parry(count) => progress.parries >= *count,
+            Self::AllOf(conditions) => conditions.iter().all(|c| c.is_met(progress)),
+            Self::AnyOf(conditions) => conditions.iter().any(|c| c.is_met(progress)),
+        }
+    }
+
+    fn describe(&self, progress: &LevelProgress) -> Vec<String> {
+        match self {
+            Self::Survive(duration) => {
+                let remaining = duration.saturating_sub(progress.elapsed).as_secs_f32();
+                vec![format!("Survive {remaining:.2}")]
+            }
+            Self::DefeatAllEnemies => {
+                vec![format!(
+                    "Defeat all enemies ({} left)",
+                    progress.enemies_remaining
+                )]
+            }
+            Self::DefeatBoss => vec!["Defeat the boss".into()],
+            Self::ReachGoal { .. } if progress.reached_goal => vec!["Goal reached".into()],
+            Self::ReachGoal { .. } => vec!["Reach the goal".into()],
+            Self::Parry(count) => {
+                vec![format!("Parry {}/{count}", progress.parries.min(*count))]
+            }
+            Self::AllOf(conditions) => conditions
+                .iter()
+                .flat_map(|c| c.describe(progress))
+                .collect(),
+            Self::AnyOf(conditions) => conditions
+                .iter()
+                .map(|c| c.describe(progress))
+                .collect::<Vec<_>>()
+                .join(&String::from("or")),
+        }
+    }
+
+    fn goal_zones(&self) -> Vec<(Vec2, f32)> {
+        match self {
+            Self::ReachGoal { position, radius } => vec![(*position, *radius)],
+            Self::AllOf(conditions) | Self::AnyOf(conditions) => {
+                conditions.iter().flat_map(|c| c.goal_zones()).collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// Ways to lose a level other than dying.
+#[derive(Clone)]
+pub enum LoseCondition {
+    /// Take more than this much damage during the level.
+    DamageTakenAbove(f32),
+    /// The win condition has to be met before this runs out.
+    TimeLimit(Duration),
+}
+
+impl LoseCondition {
+    fn is_met(&self, progress: &LevelProgress) -> bool {
+        match self {
+            Self::DamageTakenAbove(damage) => progress.damage_taken > *damage,
+            Self::TimeLimit(duration) => progress.elapsed >= *duration,
+        }
+    }
+
+    fn describe(&self, progress: &LevelProgress) -> String {
+        match self {
+            Self::DamageTakenAbove(damage) => {
+                format!("Damage taken {:.0}/{damage:.0}", progress.damage_taken)
+            }
+            Self::TimeLimit(duration) => {
+                let remaining = duration.saturating_sub(progress.elapsed).as_secs_f32();
+                format!("Time left {remaining:.2}")
+            }
+        }
+    }
+}
+
+/// What the player did so far in the current level.
+#[derive(Resource, Default)]
+pub struct LevelProgress {
+    pub elapsed: Duration,
+    pub enemies_remaining: usize,
+    pub bosses_remaining: usize,
+    /// Whether the level's boss was ever there.
+    pub boss_appeared: bool,
+    pub reached_goal: bool,
+    pub parries: u32,
+    pub damage_taken: f32,
+    /// Whether the level was already won or lost, so that it doesn't end twice.
+    finished: bool,
+}
+
+#[derive(Component)]
+struct GoalZone {
+    radius: f32,
+}
+
+#[derive(Component)]
+struct ObjectiveText;
+
+fn spawn_objective_display(mut commands: Commands) {
+    // TODO: Do I want to export a `spawn_at_position` function from the world_ui module?
+    let world_position = vec3(-50., 65.5, 0.);
+    let axis_entity = commands
+        .spawn((
+            Transform::from_translation(world_position),
+            Name::new("Objectives Axis"),
+        ))
+        .id();
+
+    commands.spawn((
+        z_index::WORLD_UI,
+        Node {
+            display: Display::Flex,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        WorldUI {
+            tracked_entity: axis_entity,
+        },
+        Name::new("Objectives WorldUI"),
+        children![(
+            Text::default(),
+            TextFont {
+                font_size: 24.0,
+                ..default()
+            },
+            ObjectiveText,
+            Name::new("Objectives"),
+        )],
+    ));
+}
+
+fn reset_progress(mut progress: ResMut<LevelProgress>) {
+    *progress = LevelProgress::default();
+}
+
+fn spawn_goal_zones(
+    level_config: Res<CurrentLevelConfig>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (position, radius) in level_config.0.win.goal_zones() {
+        commands.spawn((
+            Mesh2d(meshes.add(Circle::new(radius))),
+            MeshMaterial2d(materials.add(ui::palette::GREEN.with_alpha(0.4))),
+            Transform::from_translation(position.extend(game_z_index::GOAL_ZONES)),
+            GoalZone { radius },
+            Name::new("Goal Zone"),
+        ));
+    }
+}
+
+fn despawn_goal_zones(q_goals: Query<Entity, With<GoalZone>>, mut commands: Commands) {
+    for entity in q_goals.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn track_progress(
+    mut progress: ResMut<LevelProgress>,
+    q_player: Query<(Entity, &Transform), With<Player>>,
+    q_goals: Query<(&GoalZone, &Transform)>,
+    q_enemies: Query<(), With<Enemy>>,
+    q_bosses: Query<(), With<Boss>>,
+    mut parry_events: EventReader<ParryEvent>,
+    mut damage_events: EventReader<DamageOccurredEvent>,
+    time: Res<Time>,
+) {
+    progress.elapsed += time.delta();
+    progress.enemies_remaining = q_enemies.iter().count();
+    progress.bosses_remaining = q_bosses.iter().count();
+    progress.boss_appeared |= progress.bosses_remaining > 0;
+    progress.parries += parry_events
+        .read()
+        .filter(|event| event.kind != ParryKind::Blocked)
+        .count() as u32;
+
+    let Ok((player, player_transform)) = q_player.single() else {
+        return;
+    };
+    progress.damage_taken += damage_events
+        .read()
+        .filter(|event| event.target_entity == player)
+        .map(|event| event.damage)
+        .sum::<f32>();
+    let player_position = player_transform.translation.xy();
+    if q_goals.iter().any(|(goal, transform)| {
+        player_position.distance(transform.translation.xy()) <= goal.radius
+    }) {
+        progress.reached_goal = true;
+    }
+}
+
+fn check_conditions(
+    mut progress: ResMut<LevelProgress>,
+    level_config: Res<CurrentLevelConfig>,
+    mut win_event: EventWriter<CombatFinishedEvent>,
+    mut lose_event: EventWriter<LoseEvent>,
+) {
+    if progress.finished {
+        return;
+    }
+    if level_config.0.lose.iter().any(|c| c.is_met(&progress)) {
+        progress.finished = true;
+        lose_event.write(LoseEvent);
+    } else if level_config.0.win.is_met(&progress) {
+        progress.finished = true;
+        win_event.write(CombatFinishedEvent);
+    }
+}
+
+fn update_objective_display(
+    mut q_text: Query<&mut Text, With<ObjectiveText>>,
+    progress: Res<LevelProgress>,
+    level_config: Res<CurrentLevelConfig>,
+) {
+    let mut lines = level_config.0.win.describe(&progress);
+    lines.extend(level_config.0.lose.iter().map(|c| c.describe(&progress)));
+    for mut text in q_text.iter_mut() {
+        text.0 = lines.join("\n");
+    }
+}
@@ -8,4 +8,5 @@ pub enum GameLayers {
     PlayerBullet,
     Bullet,
     Wall,
+    Enemy,
 }
@@ -0,0 +1,127 @@
+use std::{path::Path, time::Duration};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{utils::resources::RunRng, AppState};
+
+use super::{
+    arena::Arena, difficulty::DifficultyLevel, game_z_index, health::HealOverTime, player::Player,
+};
+
+const SPAWN_INTERVAL: Duration = Duration::from_secs(10);
+const PICKUP_SIZE: f32 = 6.;
+/// How far from the walls the pickups spawn, so that they can be reached.
+const WALL_MARGIN: f32 = 10.;
+const HEAL_AMOUNT: f32 = 4.;
+const HEAL_PER_SECOND: f32 = 2.;
+
+/// Hearts that show up in the arena every once in a while, and heal the player over time.
+pub fn plugin(app: &mut App) {
+    app.init_resource::<PickupSpawner>()
+        .add_systems(OnEnter(AppState::Defending), seed_pickups)
+        .add_systems(
+            Update,
+            (spawn_pickups, collect_pickups).run_if(in_state(AppState::Defending)),
+        )
+        .add_systems(OnExit(AppState::Defending), despawn_pickups);
+}
+
+#[derive(Resource)]
+struct PickupSpawner {
+    timer: Timer,
+    /// Separate from the `RunRng`, since the number of pickups depends on how long the fights take,
+    /// and they would change everything that's drawn after them.
+    rng: StdRng,
+}
+
+impl Default for PickupSpawner {
+    fn default() -> Self {
+        Self {
+            timer: Timer::new(SPAWN_INTERVAL, TimerMode::Repeating),
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+}
+
+#[derive(Component)]
+struct HealthPickup;
+
+/// Every level of a run has its own pickups, the same ones every time it's played.
+fn seed_pickups(
+    run_rng: Res<RunRng>,
+    level: Res<DifficultyLevel>,
+    mut spawner: ResMut<PickupSpawner>,
+) {
+    spawner.rng = StdRng::seed_from_u64(run_rng.seed().wrapping_add(level.0 as u64));
+}
+
+fn spawn_pickups(
+    mut spawner: ResMut<PickupSpawner>,
+    q_pickups: Query<(), With<HealthPickup>>,
+    arena: Res<Arena>,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    spawner.timer.tick(time.delta());
+    // One at a time is enough
+    if !spawner.timer.just_finished() || !q_pickups.is_empty() {
+        return;
+    }
+    let half_size = (arena.size / 2. - WALL_MARGIN).max(Vec2::ZERO);
+    let rng = &mut spawner.rng;
+    let position = Vec2::new(
+        rng.gen_range(-half_size.x..=half_size.x),
+        rng.gen_range(-half_size.y..=half_size.y),
+    );
+    commands.spawn((
+        Sprite {
+            image: asset_server.load(Path::new("sprites/upgrades/apple.png")),
+            custom_size: Some(Vec2::splat(PICKUP_SIZE)),
+            ..default()
+        },
+        Transform::from_translation(position.extend(game_z_index::PICKUPS)),
+        HealthPickup,
+        Name::new("Health Pickup"),
+    ));
+}
+
+fn collect_pickups(
+    q_pickups: Query<(Entity, &Transform), With<HealthPickup>>,
+    q_players: Query<(Entity, &Transform), With<Player>>,
+    mut commands: Commands,
+) {
+    for (pickup, pickup_transform) in q_pickups.iter() {
+        let collector = q_players.iter().find(|(_, transform)| {
+            transform
+                .translation
+                .xy()
+                .distance(pickup_transform.translation.xy())
+                < PICKUP_SIZE
+        });
+        let Some((player, _)) = collector else {
+            continue;
+        };
+        commands.entity(pickup).despawn();
+        commands
+            .entity(player)
+            .entry::<HealOverTime>()
+            .or_insert(HealOverTime {
+                remaining: 0.,
+                per_second: HEAL_PER_SECOND,
+            })
+            .and_modify(|mut heal_over_time| heal_over_time.remaining += HEAL_AMOUNT);
+    }
+}
+
+fn despawn_pickups(
+    q_pickups: Query<Entity, With<HealthPickup>>,
+    mut spawner: ResMut<PickupSpawner>,
+    mut commands: Commands,
+) {
+    for pickup in q_pickups.iter() {
+        commands.entity(pickup).despawn();
+    }
+    spawner.timer.reset();
+}
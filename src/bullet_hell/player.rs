@@ -2,28 +2,41 @@ use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_inspector_egui::prelude::ReflectInspectorOptions;
 use bevy_inspector_egui::InspectorOptions;
+use serde::Deserialize;
 
 use crate::{
     game_config::GameConfig,
-    ui::lose_screen::LoseEvent,
     upgrades::{UpgradesReceiver, UpgradesReceiverFaction},
     utils::{input::get_input_direction, kinematic_controller::KinematicController},
-    AppState,
+    DefendingState, RewindRunEvent,
 };
 
 use super::{
-    dash::Dasher, game_ui::healthbar::spawn_healthbar, game_z_index, health::Health, physics_layers,
+    abilities3::TensionPoints,
+    arena::ArenaWall,
+    dash::Dasher,
+    death::Revive,
+    game_ui::healthbar::spawn_healthbar,
+    game_z_index,
+    health::{
+        Armor, HealOverTime, Health, HitInvulnerability, Overheal, Regeneration, Resistances,
+        Shield,
+    },
+    physics_layers,
 };
 
 pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Player>()
+            .register_type::<SoulMode>()
             .add_systems(Startup, setup_player)
             .add_systems(
                 Update,
-                (character_movement, player_death).run_if(in_state(AppState::Defending)),
-            );
+                character_movement.run_if(in_state(DefendingState::Fighting)),
+            )
+            .add_systems(Update, soul_mode_color)
+            .add_observer(reset_player);
     }
 }
 
@@ -34,6 +47,48 @@ pub struct Player {
     pub speed: f32,
 }
 
+/// How the player moves, like the soul colors in Undertale/Deltarune.
+#[derive(Component, InspectorOptions, Default, Reflect, Deserialize, Clone, Copy, PartialEq)]
+#[reflect(Component, InspectorOptions)]
+pub enum SoulMode {
+    /// Moves freely in every direction.
+    #[default]
+    Red,
+    /// Falls to the floor of the arena, and can only jump up from it.
+    Blue,
+}
+
+impl SoulMode {
+    fn color(&self) -> Color {
+        match self {
+            Self::Red => Color::srgb_u8(165, 75, 251),
+            Self::Blue => Color::srgb_u8(60, 90, 250),
+        }
+    }
+}
+
+const BLUE_SOUL_GRAVITY: f32 = 400.;
+const BLUE_SOUL_JUMP_SPEED: f32 = 160.;
+
+/// The parts of the player that change during a run, as they are when it starts.
+fn initial_player_state(config: &GameConfig) -> impl Bundle {
+    (
+        Transform::from_translation(Vec3::Z * game_z_index::PLAYERS),
+        LinearVelocity::default(),
+        Dasher {
+            dash_amount: 50.,
+            // dash_duration: Duration::from_secs_f32(0.5),
+            dash_speed: 200.,
+            last_direction: Vec3::Y,
+        },
+        Player { speed: 100.0 },
+        SoulMode::Red,
+        Health::new(if config.infinite_hp { 100000. } else { 20. }),
+        HitInvulnerability::default(),
+        TensionPoints::new(100.),
+    )
+}
+
 fn setup_player(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -43,15 +98,8 @@ fn setup_player(
     let sprite_size = 7.5;
     let player_commands = commands.spawn((
         Mesh2d(meshes.add(Rectangle::new(sprite_size, sprite_size))),
-        MeshMaterial2d(materials.add(Color::srgb_u8(165, 75, 251))),
-        Transform::from_translation(Vec3::Z * game_z_index::PLAYERS),
-        Dasher {
-            dash_amount: 50.,
-            // dash_duration: Duration::from_secs_f32(0.5),
-            dash_speed: 200.,
-        },
-        Player { speed: 100.0 },
-        Health::new(if config.infinite_hp { 100000. } else { 20. }),
+        MeshMaterial2d(materials.add(SoulMode::Red.color())),
+        initial_player_state(&config),
         UpgradesReceiver {
             factions: UpgradesReceiverFaction::Player,
         },
@@ -64,33 +112,73 @@ fn setup_player(
             ),
             Collider::rectangle(sprite_size, sprite_size),
             RigidBody::Kinematic,
-            LinearVelocity::default(),
             CollisionEventsEnabled,
+            CollidingEntities::default(),
         ),
     ));
     let player_entity = player_commands.id();
     spawn_healthbar(&mut commands, player_entity);
 }
 
+fn reset_player(
+    _trigger: Trigger<RewindRunEvent>,
+    q_player: Query<Entity, With<Player>>,
+    config: Res<GameConfig>,
+    mut commands: Commands,
+) {
+    for entity in q_player.iter() {
+        commands
+            .entity(entity)
+            .insert(initial_player_state(&config))
+            // The player starts without them, and only gets them from upgrades
+            .remove::<(
+                Shield,
+                Armor,
+                Resistances,
+                Regeneration,
+                HealOverTime,
+                Overheal,
+                Revive,
+            )>();
+    }
+}
+
 fn character_movement(
     mut characters: Query<
-        (&mut LinearVelocity, &Player),
+        (&mut LinearVelocity, &Player, &SoulMode, &CollidingEntities),
         (ControllablePlayerFilter, With<KinematicController>),
     >,
+    q_walls: Query<&ArenaWall>,
     input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
 ) {
-    for (mut velocity, player) in &mut characters {
-        velocity.0 = get_input_direction(&input).xy() * player.speed;
+    for (mut velocity, player, soul_mode, colliding_entities) in &mut characters {
+        let direction = get_input_direction(&input).xy();
+        match soul_mode {
+            SoulMode::Red => velocity.0 = direction * player.speed,
+            SoulMode::Blue => {
+                velocity.x = direction.x * player.speed;
+                let grounded = q_walls
+                    .iter_many(colliding_entities.iter())
+                    .any(|wall| *wall == ArenaWall::Floor);
+                if grounded && direction.y > 0. {
+                    velocity.y = BLUE_SOUL_JUMP_SPEED;
+                } else {
+                    velocity.y -= BLUE_SOUL_GRAVITY * time.delta_secs();
+                }
+            }
+        }
     }
 }
 
-fn player_death(
-    health_query: Query<&Health, With<Player>>,
-    mut lose_event: EventWriter<LoseEvent>,
+fn soul_mode_color(
+    q_souls: Query<(&SoulMode, &MeshMaterial2d<ColorMaterial>), Changed<SoulMode>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    for health in health_query.iter() {
-        if health.health <= 0. {
-            lose_event.write(LoseEvent);
+    for (soul_mode, material) in q_souls.iter() {
+        if let Some(material) = materials.get_mut(material.id()) {
+            // Keep the alpha, which the invulnerability effect uses
+            material.color = soul_mode.color().with_alpha(material.color.alpha());
         }
     }
 }
@@ -6,7 +6,10 @@ use bevy_inspector_egui::prelude::*;
 use bevy_tween::tween::TargetAsset;
 use bevy_tween::{combinator::*, prelude::*, tween_event_system};
 
-use crate::bullet_hell::bullet::Bullet;
+use crate::bullet_hell::abilities3::{ParryEvent, ParryKind};
+use crate::bullet_hell::bullet::{Bullet, PlayerBullet};
+use crate::bullet_hell::enemies::{laser::Laser, Enemy};
+use crate::bullet_hell::health::{DamageType, TryDamageEvent};
 use crate::bullet_hell::physics_layers;
 
 pub struct SwordPlugin;
@@ -15,14 +18,21 @@ impl Plugin for SwordPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(DefaultTweenPlugins)
             .add_event::<TweenEvent<SwordAnimationEvent>>()
+            .add_event::<SwordHitEvent>()
+            .register_type::<SwordAttack>()
+            .register_type::<Parrying>()
             .add_systems(
                 Update,
                 (
                     sword_animation_events,
                     tween_event_system::<SwordAnimationEvent>,
+                    sword_active_window,
                 ),
             )
-            .add_systems(FixedUpdate, bullet_collision);
+            .add_systems(
+                FixedUpdate,
+                (bullet_collision, laser_collision, enemy_collision),
+            );
     }
 }
 
@@ -30,27 +40,55 @@ impl Plugin for SwordPlugin {
 #[reflect(Component, InspectorOptions)]
 struct SwordAttack {
     lifetime: Timer,
+    /// The part of the animation during which the sword actually parries, as (start, end).
+    active_window: (Duration, Duration),
+    /// Parries that happen between the start of the active window and this point are perfect parries.
+    perfect_window_end: Duration,
 }
 
 impl SwordAttack {
-    pub fn new(lifetime: Duration) -> Self {
+    pub fn new(
+        lifetime: Duration,
+        active_window: (Duration, Duration),
+        perfect_window_end: Duration,
+    ) -> Self {
         Self {
             lifetime: Timer::new(lifetime, TimerMode::Once),
+            active_window,
+            perfect_window_end,
         }
     }
+
+    fn is_active(&self) -> bool {
+        let elapsed = self.lifetime.elapsed();
+        self.active_window.0 <= elapsed && elapsed < self.active_window.1
+    }
+
+    fn is_perfect(&self) -> bool {
+        self.is_active() && self.lifetime.elapsed() < self.perfect_window_end
+    }
 }
 
 impl Default for SwordAttack {
     fn default() -> Self {
-        Self::new(Duration::from_secs_f32(0.5))
+        // Matches the length of the animation in `spawn_sword`
+        Self::new(secs(0.2), (secs(0.03), secs(0.15)), secs(0.08))
     }
 }
 
+/// Added to the sword's owner while the sword is in its active window.
+/// Used for things that the sword blocks without touching them, like lasers.
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct Parrying;
+
 #[derive(Clone)]
 enum SwordAnimationEvent {
     Finished,
 }
 
+const SWORD_DAMAGE: f32 = 5.;
+
 fn secs(secs: f32) -> Duration {
     Duration::from_secs_f32(secs)
 }
@@ -72,7 +110,7 @@ pub fn spawn_sword(
     let size = 15.;
 
     commands.entity(*parent).with_children(|builder| {
-        // TODO: Improve the animation, make the sword only active for part of the animation
+        // TODO: Improve the animation
         builder
             .spawn((
                 SwordAttack::default(),
@@ -83,12 +121,17 @@ pub fn spawn_sword(
                 (
                     CollisionLayers::new(
                         physics_layers::GameLayers::PlayerBullet,
-                        physics_layers::GameLayers::Bullet,
+                        [
+                            physics_layers::GameLayers::Bullet,
+                            physics_layers::GameLayers::Enemy,
+                        ],
                     ),
                     RigidBody::Kinematic,
                     Collider::circle(size),
                     Sensor,
                     CollisionEventsEnabled,
+                    // Enabled once the active window starts
+                    ColliderDisabled,
                 ),
             ))
             .animation()
@@ -125,36 +168,132 @@ fn sword_animation_events(
     });
 }
 
+fn sword_active_window(
+    mut q_swords: Query<(Entity, &ChildOf, &mut SwordAttack, Has<ColliderDisabled>)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, child_of, mut sword, is_disabled) in q_swords.iter_mut() {
+        sword.lifetime.tick(time.delta());
+        if sword.is_active() && is_disabled {
+            commands.entity(entity).remove::<ColliderDisabled>();
+            commands.entity(child_of.parent()).insert(Parrying);
+        } else if !sword.is_active() && !is_disabled {
+            commands.entity(entity).insert(ColliderDisabled);
+            commands.entity(child_of.parent()).remove::<Parrying>();
+        }
+    }
+}
+
 fn bullet_collision(
     mut commands: Commands,
     mut contact_events: EventReader<CollisionStarted>,
-    names: Query<&Name>,
-    bullets: Query<Entity, With<Bullet>>,
-    swords: Query<Has<SwordAttack>>,
-) -> Result {
+    bullets: Query<(&Bullet, &LinearVelocity)>,
+    swords: Query<(&SwordAttack, &ChildOf)>,
+    mut parry_events: EventWriter<ParryEvent>,
+) {
     // TODO: How do I describe which entities will be destroyed by this? Just anything hittable and hostile?
     for event in contact_events.read() {
         let CollisionStarted(entity1, entity2) = event;
-        // TODO: get this working with swapped entity orders???
-        let names = [
-            names
-                .get(*entity1)
-                .map_or(format!("{entity1}"), ToString::to_string),
-            names
-                .get(*entity2)
-                .map_or(format!("{entity2}"), ToString::to_string),
-        ];
-        println!("Sword collision {names:?}");
-        if swords.get(*entity1)? {
-            if let Ok(bullet_entity) = bullets.get(*entity2) {
-                commands.entity(bullet_entity).despawn();
-            }
+        let ((sword, sword_child_of), bullet_entity, (bullet, velocity)) =
+            if let (Ok(sword), Ok(bullet)) = (swords.get(*entity1), bullets.get(*entity2)) {
+                (sword, *entity2, bullet)
+            } else if let (Ok(sword), Ok(bullet)) = (swords.get(*entity2), bullets.get(*entity1)) {
+                (sword, *entity1, bullet)
+            } else {
+                continue;
+            };
+        if !sword.is_active() {
+            continue;
+        }
+
+        let kind = if sword.is_perfect() {
+            // Send the bullet back where it came from, now hurting enemies instead of the player
+            commands
+                .entity(bullet_entity)
+                .try_remove::<Bullet>()
+                .try_insert((
+                    PlayerBullet {
+                        damage: bullet.damage,
+                        source: Some(sword_child_of.parent()),
+                    },
+                    LinearVelocity(-velocity.0),
+                    CollisionLayers::new(
+                        physics_layers::GameLayers::PlayerBullet,
+                        physics_layers::GameLayers::Enemy,
+                    ),
+                    CollisionEventsEnabled,
+                ));
+            ParryKind::Perfect
+        } else {
+            commands.entity(bullet_entity).try_despawn();
+            ParryKind::Normal
+        };
+        parry_events.write(ParryEvent {
+            parrier: sword_child_of.parent(),
+            parried: bullet_entity,
+            kind,
+        });
+    }
+}
+
+fn laser_collision(
+    mut contact_events: EventReader<CollisionStarted>,
+    lasers: Query<(), With<Laser>>,
+    swords: Query<(&SwordAttack, &ChildOf)>,
+    mut parry_events: EventWriter<ParryEvent>,
+) {
+    for event in contact_events.read() {
+        let CollisionStarted(entity1, entity2) = event;
+        let ((sword, sword_child_of), laser_entity) =
+            if let (Ok(sword), Ok(())) = (swords.get(*entity1), lasers.get(*entity2)) {
+                (sword, *entity2)
+            } else if let (Ok(sword), Ok(())) = (swords.get(*entity2), lasers.get(*entity1)) {
+                (sword, *entity1)
+            } else {
+                continue;
+            };
+        if sword.is_active() {
+            // The damage itself is blocked by the `Parrying` component, see the laser module
+            parry_events.write(ParryEvent {
+                parrier: sword_child_of.parent(),
+                parried: laser_entity,
+                kind: ParryKind::Blocked,
+            });
         }
-        if swords.get(*entity2)? {
-            if let Ok(bullet_entity) = bullets.get(*entity1) {
-                commands.entity(bullet_entity).despawn();
+    }
+}
+
+#[derive(Event)]
+pub struct SwordHitEvent {
+    pub attacker: Entity,
+    pub target: Entity,
+}
+
+fn enemy_collision(
+    mut contact_events: EventReader<CollisionStarted>,
+    enemies: Query<(), With<Enemy>>,
+    swords: Query<(&SwordAttack, &ChildOf)>,
+    mut damage_events: EventWriter<TryDamageEvent>,
+    mut hit_events: EventWriter<SwordHitEvent>,
+) {
+    for CollisionStarted(entity1, entity2) in contact_events.read() {
+        for (sword_entity, enemy_entity) in [(*entity1, *entity2), (*entity2, *entity1)] {
+            let Ok((sword, child_of)) = swords.get(sword_entity) else {
+                continue;
+            };
+            if sword.is_active() && enemies.contains(enemy_entity) {
+                damage_events.write(TryDamageEvent {
+                    target_entity: enemy_entity,
+                    damage: SWORD_DAMAGE,
+                    damage_type: DamageType::Sword,
+                    source: Some(child_of.parent()),
+                });
+                hit_events.write(SwordHitEvent {
+                    attacker: child_of.parent(),
+                    target: enemy_entity,
+                });
             }
         }
     }
-    Ok(())
 }
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::{
+    upgrades::{HookTrigger, UpgradeHooks},
+    AppState,
+};
+
+use super::{
+    abilities3::{ParryEvent, ParryKind},
+    bullet::GrazeEvent,
+    dash::DashStartedEvent,
+    health::DamageOccurredEvent,
+    level::LevelFinishedEvent,
+    sword::SwordHitEvent,
+};
+
+/// Runs the upgrades' hooks when the gameplay events they wait for happen.
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(AppState::Defending), on_level_started)
+        .add_systems(
+            Update,
+            (
+                on_damaged,
+                on_dash,
+                on_sword_hit,
+                on_parry,
+                on_level_finished,
+                on_graze,
+            ),
+        );
+}
+
+fn fire_hooks(
+    q_hooks: &Query<&UpgradeHooks>,
+    owner: Entity,
+    trigger: HookTrigger,
+    commands: &mut Commands,
+) {
+    if let Ok(hooks) = q_hooks.get(owner) {
+        hooks.fire(trigger, owner, commands);
+    }
+}
+
+fn on_damaged(
+    mut events: EventReader<DamageOccurredEvent>,
+    q_hooks: Query<&UpgradeHooks>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        fire_hooks(
+            &q_hooks,
+            event.target_entity,
+            HookTrigger::Damaged,
+            &mut commands,
+        );
+    }
+}
+
+fn on_dash(
+    mut events: EventReader<DashStartedEvent>,
+    q_hooks: Query<&UpgradeHooks>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        fire_hooks(&q_hooks, event.dasher, HookTrigger::Dash, &mut commands);
+    }
+}
+
+fn on_sword_hit(
+    mut events: EventReader<SwordHitEvent>,
+    q_hooks: Query<&UpgradeHooks>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        fire_hooks(
+            &q_hooks,
+            event.attacker,
+            HookTrigger::SwordHit,
+            &mut commands,
+        );
+    }
+}
+
+fn on_parry(
+    mut events: EventReader<ParryEvent>,
+    q_hooks: Query<&UpgradeHooks>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        if event.kind != ParryKind::Blocked {
+            fire_hooks(&q_hooks, event.parrier, HookTrigger::Parry, &mut commands);
+        }
+    }
+}
+
+fn on_level_started(q_hooks: Query<(Entity, &UpgradeHooks)>, mut commands: Commands) {
+    for (owner, hooks) in q_hooks.iter() {
+        hooks.fire(HookTrigger::LevelStarted, owner, &mut commands);
+    }
+}
+
+fn on_level_finished(
+    mut events: EventReader<LevelFinishedEvent>,
+    q_hooks: Query<(Entity, &UpgradeHooks)>,
+    mut commands: Commands,
+) {
+    for _ in events.read() {
+        for (owner, hooks) in q_hooks.iter() {
+            hooks.fire(HookTrigger::LevelFinished, owner, &mut commands);
+        }
+    }
+}
+
+fn on_graze(
+    mut events: EventReader<GrazeEvent>,
+    q_hooks: Query<&UpgradeHooks>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        fire_hooks(&q_hooks, event.grazer, HookTrigger::Graze, &mut commands);
+    }
+}
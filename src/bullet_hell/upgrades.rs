@@ -1,22 +1,40 @@
-use std::path::Path;
+use std::{path::Path, time::Duration};
 
-use bevy::{color::palettes, ecs::system::BoxedSystem, prelude::*};
+use bevy::{
+    color::palettes,
+    ecs::system::{BoxedSystem, SystemId},
+    prelude::*,
+};
 
 use crate::{
-    upgrades::{GlobalUpgrade, Upgrade, UpgradesReceiverFaction},
-    utils::resources::SelectionsPool,
+    ui::level_transition::ShopType,
+    upgrades::{
+        GlobalUpgrade, HookTrigger, Synergy, SynergyRegistry, Upgrade, UpgradeHook, UpgradeHooks,
+        UpgradeRegistry, UpgradesReceiverFaction,
+    },
 };
 
-use super::{health::Health, player::Player};
+use super::{
+    abilities3::TensionPoints,
+    bullet::{Bullet, PlayerBullet},
+    dash::{DashStrike, Dasher},
+    death::{Revive, ReviveUsed},
+    enemies::{registry::SpawnEnemy, BulletSpeedMultiplier},
+    health::{
+        Armor, DamageType, HealEvent, Health, HitInvulnerability, Overheal, Regeneration,
+        Resistances, Shield,
+    },
+    player::Player,
+};
 
 pub fn populate_upgrades_pool(world: &mut World) {
-    // mut upgrade_pool: ResMut<SelectionsPool<GlobalUpgrade>>
     // TODO: Should this be a normal function called as an argument for app.insert_resource?
     // That would make it safer I guess (won't allow me to forget to call it)
     let unimplemented_id = world.register_system(unimplemented_system);
     let upgrades = vec![
         GlobalUpgrade {
             upgrade: Upgrade {
+                id: "apple",
                 apply_upgrade: world
                     .register_boxed_system(AddHealthUpgrade::new(10.).into_boxed_system()),
                 name: "Apple",
@@ -27,6 +45,7 @@ pub fn populate_upgrades_pool(world: &mut World) {
         },
         GlobalUpgrade {
             upgrade: Upgrade {
+                id: "sword",
                 apply_upgrade: unimplemented_id,
                 name: "Sword",
                 description: "Get a cool attack",
@@ -36,6 +55,7 @@ pub fn populate_upgrades_pool(world: &mut World) {
         },
         GlobalUpgrade {
             upgrade: Upgrade {
+                id: "yellow_boxes",
                 apply_upgrade: world.register_system(make_bullets_yellow),
                 name: "Yellow Boxes",
                 description: "Test upgrade - make all enemy bullets yellow",
@@ -45,6 +65,7 @@ pub fn populate_upgrades_pool(world: &mut World) {
         },
         GlobalUpgrade {
             upgrade: Upgrade {
+                id: "cool_shoes",
                 apply_upgrade: world.register_system(speed_player_up_by_10_percent),
                 name: "Cool Shoes",
                 description: "Speed player up by x1.1",
@@ -52,11 +73,250 @@ pub fn populate_upgrades_pool(world: &mut World) {
             },
             receiver_factions: UpgradesReceiverFaction::Player,
         },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "battering_ram",
+                apply_upgrade: world.register_system(add_dash_strike),
+                name: "Battering Ram",
+                description: "Dashing through enemies hurts them and destroys bullets",
+                icon_texture: Path::new("sprites/upgrades/minecart.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "heart_container",
+                apply_upgrade: world
+                    .register_boxed_system(AddHealthUpgrade::new(25.).into_boxed_system()),
+                name: "Heart Container",
+                description: "Gain 25 max HP and heal them",
+                icon_texture: Path::new("sprites/upgrades/apple.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "rocket_shoes",
+                apply_upgrade: world.register_system(speed_player_up_by_40_percent),
+                name: "Rocket Shoes",
+                description: "Speed player up by x1.4",
+                icon_texture: Path::new("sprites/upgrades/minecart.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "energy_shield",
+                apply_upgrade: world.register_system(add_energy_shield),
+                name: "Energy Shield",
+                description:
+                    "A 5 point shield, which recharges when you don't get hit for 3 seconds",
+                icon_texture: Path::new("sprites/upgrades/sword_diamond.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "plating",
+                apply_upgrade: world.register_system(add_1_armor),
+                name: "Plating",
+                description: "Take 1 less damage from every hit",
+                icon_texture: Path::new("sprites/upgrades/minecart.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "mirror_coating",
+                apply_upgrade: world.register_system(halve_laser_damage),
+                name: "Mirror Coating",
+                description: "Lasers deal half damage",
+                icon_texture: Path::new("sprites/upgrades/yellow.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "regeneration",
+                apply_upgrade: world.register_system(add_regeneration),
+                name: "Regeneration",
+                description: "Heal 1 HP every 4 seconds during fights",
+                icon_texture: Path::new("sprites/upgrades/apple.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "overflowing_cup",
+                apply_upgrade: world.register_system(allow_overheal),
+                name: "Overflowing Cup",
+                description: "Healing can go up to 10 HP over your max, which slowly fades",
+                icon_texture: Path::new("sprites/upgrades/apple.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "phoenix_feather",
+                apply_upgrade: world.register_system(add_revive),
+                name: "Phoenix Feather",
+                description: "Come back with half your HP the first time you die",
+                icon_texture: Path::new("sprites/upgrades/apple.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "vampiric_parry",
+                apply_upgrade: register_hook(
+                    world,
+                    "vampiric_parry",
+                    HookTrigger::Parry,
+                    heal_1_hp,
+                ),
+                name: "Vampiric Parry",
+                description: "Parrying heals 1 HP",
+                icon_texture: Path::new("sprites/upgrades/apple.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "shockwave",
+                apply_upgrade: register_hook(
+                    world,
+                    "shockwave",
+                    HookTrigger::Dash,
+                    destroy_nearby_bullets,
+                ),
+                name: "Shockwave",
+                description: "Dashing destroys the bullets around you",
+                icon_texture: Path::new("sprites/upgrades/yellow.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+        GlobalUpgrade {
+            upgrade: Upgrade {
+                id: "close_call",
+                apply_upgrade: register_hook(
+                    world,
+                    "close_call",
+                    HookTrigger::Graze,
+                    gain_5_tension,
+                ),
+                name: "Close Call",
+                description: "Bullets that barely miss you give you 5 TP",
+                icon_texture: Path::new("sprites/upgrades/yellow.png"),
+            },
+            receiver_factions: UpgradesReceiverFaction::Player,
+        },
+    ];
+
+    // The strong upgrades come with a curse, which is applied together with them
+    let curses = [
+        (
+            "heart_container",
+            GlobalUpgrade {
+                upgrade: Upgrade {
+                    id: "faster_bullets",
+                    apply_upgrade: world.register_system(speed_bullets_up_by_25_percent),
+                    name: "Faster Bullets",
+                    description: "Enemy bullets fly x1.25 faster",
+                    icon_texture: Path::new("sprites/upgrades/yellow.png"),
+                },
+                receiver_factions: UpgradesReceiverFaction::Enemy,
+            },
+        ),
+        (
+            "battering_ram",
+            GlobalUpgrade {
+                upgrade: Upgrade {
+                    id: "reinforcements",
+                    apply_upgrade: world.register_system(spawn_reinforcement),
+                    name: "Reinforcements",
+                    description: "Another cannon joins the enemies",
+                    icon_texture: Path::new("sprites/upgrades/yellow.png"),
+                },
+                // It adds an enemy rather than changing one, so it's applied once - through the player
+                receiver_factions: UpgradesReceiverFaction::Player,
+            },
+        ),
+        (
+            "rocket_shoes",
+            GlobalUpgrade {
+                upgrade: Upgrade {
+                    id: "fragile",
+                    apply_upgrade: world.register_system(halve_hit_invulnerability),
+                    name: "Fragile",
+                    description: "Invulnerability after getting hit lasts half as long",
+                    icon_texture: Path::new("sprites/upgrades/yellow.png"),
+                },
+                receiver_factions: UpgradesReceiverFaction::Player,
+            },
+        ),
+    ];
+
+    let synergies = [
+        Synergy {
+            name: "Sprinter",
+            requires: &["dash", "cool_shoes"],
+            bonus: GlobalUpgrade {
+                upgrade: Upgrade {
+                    id: "sprinter",
+                    apply_upgrade: world.register_system(lengthen_dash_by_50_percent),
+                    name: "Sprinter",
+                    description: "Dash x1.5 further",
+                    icon_texture: Path::new("sprites/upgrades/minecart.png"),
+                },
+                receiver_factions: UpgradesReceiverFaction::Player,
+            },
+        },
+        Synergy {
+            name: "Juggernaut",
+            requires: &["dash", "battering_ram"],
+            bonus: GlobalUpgrade {
+                upgrade: Upgrade {
+                    id: "juggernaut",
+                    apply_upgrade: world.register_system(double_dash_strike_damage),
+                    name: "Juggernaut",
+                    description: "Dashing through enemies hurts them twice as much",
+                    icon_texture: Path::new("sprites/upgrades/minecart.png"),
+                },
+                receiver_factions: UpgradesReceiverFaction::Player,
+            },
+        },
+        Synergy {
+            name: "Balanced Diet",
+            requires: &["apple", "heart_container"],
+            bonus: GlobalUpgrade {
+                upgrade: Upgrade {
+                    id: "balanced_diet",
+                    apply_upgrade: world
+                        .register_boxed_system(AddHealthUpgrade::new(10.).into_boxed_system()),
+                    name: "Balanced Diet",
+                    description: "Gain 10 more max HP and heal them",
+                    icon_texture: Path::new("sprites/upgrades/apple.png"),
+                },
+                receiver_factions: UpgradesReceiverFaction::Player,
+            },
+        },
     ];
 
-    let mut upgrade_pool = world.resource_mut::<SelectionsPool<GlobalUpgrade>>();
+    // They're added to the shop's pool once they're unlocked
+    let mut registry = world.resource_mut::<UpgradeRegistry>();
     for upgrade in upgrades {
-        upgrade_pool.add_item(upgrade);
+        registry.register(upgrade, ShopType::Upgrades);
+    }
+    for (upgrade_id, curse) in curses {
+        registry.register_curse(upgrade_id, curse);
+    }
+    // The bonuses are registered too, so that saved runs can get them back
+    for synergy in synergies.iter() {
+        registry.register_unsold(synergy.bonus.clone());
+    }
+    let mut synergy_registry = world.resource_mut::<SynergyRegistry>();
+    for synergy in synergies {
+        synergy_registry.register(synergy);
     }
 }
 
@@ -109,3 +369,145 @@ fn speed_player_up_by_10_percent(In(entity): In<Entity>, mut q_player: Query<&mu
         player.speed *= 1.1;
     }
 }
+
+fn speed_player_up_by_40_percent(In(entity): In<Entity>, mut q_player: Query<&mut Player>) {
+    if let Ok(mut player) = q_player.get_mut(entity) {
+        player.speed *= 1.4;
+    }
+}
+
+fn speed_bullets_up_by_25_percent(
+    In(entity): In<Entity>,
+    mut q_bullet_speed: Query<&mut BulletSpeedMultiplier>,
+) {
+    if let Ok(mut bullet_speed) = q_bullet_speed.get_mut(entity) {
+        bullet_speed.0 *= 1.25;
+    }
+}
+
+fn spawn_reinforcement(In(_entity): In<Entity>, mut commands: Commands) {
+    commands.queue(SpawnEnemy::new(
+        "stationary_cannon",
+        Vec2::new(-100., -70.),
+        Vec2::new(1., 1.),
+    ));
+}
+
+fn halve_hit_invulnerability(
+    In(entity): In<Entity>,
+    mut q_invulnerability: Query<&mut HitInvulnerability>,
+) {
+    if let Ok(mut hit_invulnerability) = q_invulnerability.get_mut(entity) {
+        hit_invulnerability.duration /= 2;
+    }
+}
+
+fn lengthen_dash_by_50_percent(In(entity): In<Entity>, mut q_dasher: Query<&mut Dasher>) {
+    if let Ok(mut dasher) = q_dasher.get_mut(entity) {
+        dasher.dash_amount *= 1.5;
+    }
+}
+
+fn double_dash_strike_damage(In(entity): In<Entity>, mut q_dash_strike: Query<&mut DashStrike>) {
+    if let Ok(mut dash_strike) = q_dash_strike.get_mut(entity) {
+        dash_strike.damage *= 2.;
+    }
+}
+
+fn add_energy_shield(In(entity): In<Entity>, mut commands: Commands) {
+    commands
+        .entity(entity)
+        .insert(Shield::new(5., 1., Duration::from_secs(3)));
+}
+
+fn add_1_armor(In(entity): In<Entity>, mut commands: Commands) {
+    commands
+        .entity(entity)
+        .entry::<Armor>()
+        .or_default()
+        .and_modify(|mut armor| armor.flat += 1.);
+}
+
+fn add_regeneration(In(entity): In<Entity>, mut commands: Commands) {
+    commands
+        .entity(entity)
+        .entry::<Regeneration>()
+        .or_default()
+        .and_modify(|mut regeneration| regeneration.per_second += 0.25);
+}
+
+fn allow_overheal(In(entity): In<Entity>, mut commands: Commands) {
+    commands.entity(entity).insert(Overheal::new(10., 1.));
+}
+
+fn add_revive(In(entity): In<Entity>, revive_used: Res<ReviveUsed>, mut commands: Commands) {
+    if !revive_used.0 {
+        commands.entity(entity).insert(Revive);
+    }
+}
+
+fn halve_laser_damage(In(entity): In<Entity>, mut commands: Commands) {
+    commands
+        .entity(entity)
+        .entry::<Resistances>()
+        .or_default()
+        .and_modify(|mut resistances| resistances.multiply(DamageType::Laser, 0.5));
+}
+
+/// Registers an `apply_upgrade` that makes `effect` run on the upgraded entity whenever `trigger`
+/// happens to it.
+fn register_hook<M>(
+    world: &mut World,
+    upgrade_id: &'static str,
+    trigger: HookTrigger,
+    effect: impl IntoSystem<In<Entity>, (), M> + 'static,
+) -> SystemId<In<Entity>> {
+    let effect = world.register_system(effect);
+    world.register_system(move |In(entity): In<Entity>, mut commands: Commands| {
+        UpgradeHooks::add_to(
+            &mut commands.entity(entity),
+            UpgradeHook {
+                upgrade_id,
+                trigger,
+                effect,
+            },
+        );
+    })
+}
+
+fn heal_1_hp(In(entity): In<Entity>, mut heal_writer: EventWriter<HealEvent>) {
+    heal_writer.write(HealEvent {
+        target_entity: entity,
+        amount: 1.,
+    });
+}
+
+fn destroy_nearby_bullets(
+    In(entity): In<Entity>,
+    q_transforms: Query<&Transform>,
+    q_bullets: Query<(Entity, &Transform), (With<Bullet>, Without<PlayerBullet>)>,
+    mut commands: Commands,
+) {
+    const RADIUS: f32 = 40.;
+    let Ok(center) = q_transforms.get(entity) else {
+        return;
+    };
+    for (bullet, transform) in q_bullets.iter() {
+        if transform.translation.xy().distance(center.translation.xy()) < RADIUS {
+            commands.entity(bullet).try_despawn();
+        }
+    }
+}
+
+fn gain_5_tension(In(entity): In<Entity>, mut q_tension: Query<&mut TensionPoints>) {
+    if let Ok(mut tension) = q_tension.get_mut(entity) {
+        tension.add(5.);
+    }
+}
+
+fn add_dash_strike(In(entity): In<Entity>, mut commands: Commands) {
+    commands.entity(entity).insert(DashStrike {
+        damage: 5.,
+        destroys_bullets: true,
+    });
+}
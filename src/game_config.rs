@@ -18,6 +18,19 @@ pub struct GameConfig {
     pub infinite_hp: bool,
     #[serde(default = "default_as_false")]
     pub physics_gizmos: bool,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Writes the stats of every run that ends to a file.
+    #[serde(default = "default_as_false")]
+    pub export_run_summary: bool,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
 }
 
 #[derive(Clone, Default, Deserialize)]
@@ -13,8 +13,12 @@ use serde::Deserialize;
 
 use bullet_hell::BulletHellPlugin;
 use game_config::GameConfig;
+use ui::item_menu::ItemMenuPlugin;
 use ui::lose_screen::LoseScreenPlugin;
 use ui::menu::MenuUI;
+use ui::pause_menu::PauseMenuPlugin;
+use ui::run_end_menu::RunEndMenuPlugin;
+use ui::synergy_popup::SynergyPopupPlugin;
 use ui::{level_transition::LevelTransitionPlugin, victory_screen::VictoryScreenPlugin};
 use upgrades::UpgradesPlugin;
 use utils::{menu_system::MenuSystemPlugin, world_ui::WorldUIPlugin};
@@ -38,6 +42,18 @@ pub enum AppState {
     Victory,
 }
 
+/// What's going on during a fight.
+#[derive(SubStates, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[source(AppState = AppState::Defending)]
+pub enum DefendingState {
+    #[default]
+    Fighting,
+    /// The player ran out of health, and the game is frozen while they die.
+    Dying,
+    /// The game is frozen behind the pause menu.
+    Paused,
+}
+
 fn main() {
     let game_config = get_config();
     let mut app = App::new();
@@ -65,24 +81,29 @@ fn main() {
     .add_plugins(EguiPlugin {
         enable_multipass_for_primary_context: true,
     })
-    .add_plugins(WorldInspectorPlugin::new().run_if(input_toggle_active(false, KeyCode::Escape)))
+    .add_plugins(WorldInspectorPlugin::new().run_if(input_toggle_active(false, KeyCode::Backquote)))
     .insert_state::<AppState>(
         game_config
             .debug
             .as_ref()
             .map_or(AppState::default(), |conf| conf.starting_state),
     )
+    .add_sub_state::<DefendingState>()
     .add_plugins(WorldUIPlugin)
     .add_plugins(BulletHellPlugin)
     .add_plugins(MetagamePlugin)
     .add_plugins(LevelTransitionPlugin)
     .add_plugins(MenuSystemPlugin)
     .add_plugins(MenuUI)
+    .add_plugins(ItemMenuPlugin)
+    .add_plugins(PauseMenuPlugin)
     .add_plugins(LoseScreenPlugin)
     .add_plugins(VictoryScreenPlugin)
+    .add_plugins(RunEndMenuPlugin)
+    .add_plugins(SynergyPopupPlugin)
     .add_plugins(UpgradesPlugin) // TODO: Should this be here?
     .add_plugins(utils::kinematic_controller::plugin)
-    .insert_resource(Money(100.0))
+    .insert_resource(Money(STARTING_MONEY))
     .add_systems(Startup, setup_camera);
     if game_config.physics_gizmos {
         app.add_plugins(PhysicsDebugPlugin::default());
@@ -116,3 +137,16 @@ fn setup_camera(mut commands: Commands) {
 
 #[derive(Resource)]
 pub struct Money(pub f32);
+
+/// The money a run starts with.
+pub const STARTING_MONEY: f32 = 100.;
+
+/// Throws away the run's progress, so that a new one can start.
+/// Always comes with a `RewindRunEvent`, which throws away the rest of the run.
+#[derive(Event)]
+pub struct ResetRunEvent;
+
+/// Takes away everything the run gave the player and clears the fight, so that the run can be
+/// rebuilt from a save. Unlike `ResetRunEvent`, it leaves the progress, enemies and stats alone.
+#[derive(Event)]
+pub struct RewindRunEvent;
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::bullet_hell::{LevelConfig, LoseCondition, WinCondition};
+
+use super::plugin::GameStep;
+
+const FIRST_LEVEL: LevelConfig = LevelConfig::from_seconds_duration(15);
+
+const SURVIVE_OR_PARRY: LevelConfig = LevelConfig::new(WinCondition::AnyOf(&[
+    WinCondition::Survive(Duration::from_secs(20)),
+    WinCondition::Parry(5),
+]));
+
+const REACH_THE_GOAL: LevelConfig = LevelConfig::new(WinCondition::AllOf(&[
+    WinCondition::Survive(Duration::from_secs(10)),
+    WinCondition::ReachGoal {
+        position: Vec2::new(30., -30.),
+        radius: 10.,
+    },
+]))
+.with_lose_conditions(&[LoseCondition::DamageTakenAbove(15.)]);
+
+const CAREFUL_SURVIVAL: LevelConfig = LevelConfig::from_seconds_duration(20)
+    .with_lose_conditions(&[LoseCondition::DamageTakenAbove(10.)]);
+
+const SURVIVE_AND_PARRY: LevelConfig = LevelConfig::new(WinCondition::AllOf(&[
+    WinCondition::Survive(Duration::from_secs(25)),
+    WinCondition::Parry(5),
+]));
+
+const BOSS_FIGHT: LevelConfig = LevelConfig::boss_fight("boss");
+
+pub struct MapNode {
+    pub step: GameStep,
+    /// The nodes of the next layer this node leads to.
+    pub next: Vec<usize>,
+}
+
+impl MapNode {
+    fn new(step: GameStep, next: &[usize]) -> Self {
+        Self {
+            step,
+            next: next.to_vec(),
+        }
+    }
+}
+
+/// The run, as layers of nodes. Each step the player picks one of the nodes of the next layer
+/// that the current node leads to, until the last layer is done.
+pub struct RunMap {
+    pub layers: Vec<Vec<MapNode>>,
+}
+
+impl RunMap {
+    pub fn node(&self, layer: usize, index: usize) -> &MapNode {
+        &self.layers[layer][index]
+    }
+
+    /// Whether the player could have picked these nodes, one per layer, starting at the first.
+    pub fn is_valid_path(&self, path: &[usize]) -> bool {
+        if path.len() > self.layers.len() {
+            return false;
+        }
+        let mut available: Vec<usize> = (0..self.layers[0].len()).collect();
+        for (layer, index) in path.iter().enumerate() {
+            if !available.contains(index) {
+                return false;
+            }
+            available = self.node(layer, *index).next.clone();
+        }
+        true
+    }
+}
+
+impl Default for RunMap {
+    fn default() -> Self {
+        use GameStep::*;
+        Self {
+            layers: vec![
+                vec![MapNode::new(Level(FIRST_LEVEL), &[0, 1])],
+                vec![
+                    MapNode::new(AbilityShop, &[0, 1]),
+                    MapNode::new(EliteLevel(CAREFUL_SURVIVAL), &[1, 2]),
+                ],
+                vec![
+                    MapNode::new(Level(SURVIVE_OR_PARRY), &[0]),
+                    MapNode::new(Event, &[0, 1]),
+                    MapNode::new(Rest, &[1]),
+                ],
+                vec![
+                    MapNode::new(Level(REACH_THE_GOAL), &[0, 1, 2]),
+                    MapNode::new(EliteLevel(SURVIVE_AND_PARRY), &[1, 2]),
+                ],
+                vec![
+                    MapNode::new(UpgradeShop, &[0]),
+                    MapNode::new(Rest, &[0]),
+                    MapNode::new(ItemShop, &[0]),
+                ],
+                vec![MapNode::new(Level(BOSS_FIGHT), &[])],
+            ],
+        }
+    }
+}
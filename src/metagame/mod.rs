@@ -1,4 +1,10 @@
+mod map;
 mod plugin;
+mod profile;
+mod save;
+mod stats;
 mod ui;
 
-pub use plugin::{MetagamePlugin, StartGameEvent};
+pub use plugin::{ChooseMapNodeEvent, MetagamePlugin, MetagameProgression};
+pub use profile::Profile;
+pub use save::{save_exists, ContinueRunEvent, RestartLevelEvent};
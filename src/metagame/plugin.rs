@@ -1,82 +1,178 @@
 use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
 
 use crate::{
-    bullet_hell::{CurrentLevelConfig, LevelConfig, LevelFinishedEvent},
+    bullet_hell::{
+        CurrentLevelConfig, DamageType, HealEvent, Health, Inventory, Item, LevelConfig,
+        LevelFinishedEvent, Player, ScaleDifficulty, TryDamageEvent,
+    },
     ui::level_transition::{EnterLevelTransitionEvent, FinishedLevelTransitionEvent, ShopType},
-    AppState,
+    utils::resources::RunRng,
+    AppState, Money, ResetRunEvent, STARTING_MONEY,
 };
 
-use super::ui;
+use super::{
+    map::{MapNode, RunMap},
+    profile::{self, Profile},
+    save, stats, ui,
+};
 
 pub struct MetagamePlugin;
 
 impl Plugin for MetagamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<StartGameEvent>()
-            .insert_resource(MetagameProgression {
-                levels: Vec::from(GAME_STEPS),
-                current_step_index: 0,
-                current_level: 0,
-            })
-            .add_systems(Startup, ui::spawn_menu)
+        app.add_plugins((profile::plugin, save::plugin, stats::plugin))
+            .add_event::<ChooseMapNodeEvent>()
+            .insert_resource(MetagameProgression::new(RunMap::default()))
+            .init_resource::<RunRng>()
+            .add_observer(reset_progression)
+            .add_systems(OnEnter(AppState::ActionMenu), ui::spawn_map)
+            .add_systems(OnExit(AppState::ActionMenu), ui::despawn_map)
             .add_systems(
                 Update,
                 (
-                    start_game.run_if(on_event::<StartGameEvent>), // TODO: This shouldn't be an event...
+                    choose_node.run_if(on_event::<ChooseMapNodeEvent>),
                     on_finished_step.run_if(on_event::<LevelFinishedEvent>),
                     on_finished_step.run_if(on_event::<FinishedLevelTransitionEvent>),
-                    ui::update_text_on_level_transition,
+                    (ui::despawn_map, ui::spawn_map).chain().run_if(
+                        in_state(AppState::ActionMenu).and(resource_changed::<MetagameProgression>),
+                    ),
                 ),
             );
     }
 }
 
+const LEVEL_REWARD: f32 = 10.;
+const ELITE_REWARD: f32 = 40.;
+const EVENT_MONEY: f32 = 30.;
+/// The chance of finding an item after a normal level. Elite levels always give one.
+const LEVEL_ITEM_CHANCE: f64 = 0.3;
+/// The fraction of the player's max health restored by a rest site.
+const REST_HEALING: f32 = 0.3;
+/// The event's trap hurts, but never kills.
+const TRAP_DAMAGE: f32 = 3.;
+
+/// Sent when the player picks one of `MetagameProgression::available_nodes` on the map.
 #[derive(Event)]
-pub struct StartGameEvent;
+pub struct ChooseMapNodeEvent {
+    pub node: usize,
+}
 
 #[derive(Clone)] // TODO - should this be clone?
 pub enum GameStep {
     Level(LevelConfig),
+    /// A harder fight, with a better reward.
+    EliteLevel(LevelConfig),
     AbilityShop,
     UpgradeShop,
+    ItemShop,
+    Rest,
+    /// Something random happens - it might be good, it might be bad.
+    Event,
 }
 
-const GAME_STEPS: [GameStep; 6] = [
-    GameStep::Level(LevelConfig::from_seconds_duration(15)),
-    GameStep::AbilityShop,
-    GameStep::Level(LevelConfig::from_seconds_duration(10)),
-    GameStep::Level(LevelConfig::from_seconds_duration(10)),
-    GameStep::UpgradeShop,
-    GameStep::Level(LevelConfig::from_seconds_duration(30)),
-];
+impl GameStep {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameStep::Level(level_config) if level_config.boss.is_some() => "Boss",
+            GameStep::Level(_) => "Fight",
+            GameStep::EliteLevel(_) => "Elite",
+            GameStep::AbilityShop => "Ability Shop",
+            GameStep::UpgradeShop => "Shop",
+            GameStep::ItemShop => "Item Shop",
+            GameStep::Rest => "Rest",
+            GameStep::Event => "?",
+        }
+    }
+}
 
 #[derive(Resource)]
 pub struct MetagameProgression {
-    levels: Vec<GameStep>,
-    pub current_step_index: usize,
+    pub map: RunMap,
+    /// The index of the node picked in each layer so far.
+    pub path: Vec<usize>,
 
     pub current_level: usize,
+    /// What happened in the last event node, to show on the map.
+    pub last_event: Option<String>,
 }
 
 impl MetagameProgression {
-    pub fn iter_levels(&self) -> impl Iterator<Item = &GameStep> {
-        self.levels.iter()
+    pub fn new(map: RunMap) -> Self {
+        Self {
+            map,
+            path: Vec::new(),
+            current_level: 0,
+            last_event: None,
+        }
     }
+
+    pub fn current_node(&self) -> Option<&MapNode> {
+        let layer = self.path.len().checked_sub(1)?;
+        Some(self.map.node(layer, self.path[layer]))
+    }
+
+    /// The indices of the nodes in the next layer that the player can pick from.
+    pub fn available_nodes(&self) -> Vec<usize> {
+        match self.current_node() {
+            None => (0..self.map.layers[0].len()).collect(),
+            Some(node) => node.next.clone(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.path.len() == self.map.layers.len()
+    }
+}
+
+/// Starts over from the first layer of the map. The run's seed is up to whoever reset the run.
+fn reset_progression(
+    _trigger: Trigger<ResetRunEvent>,
+    mut progression: ResMut<MetagameProgression>,
+    mut money: ResMut<Money>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    *progression = MetagameProgression::new(RunMap::default());
+    money.0 = STARTING_MONEY;
+    next_state.set(AppState::ActionMenu);
 }
 
-fn start_game(
+fn choose_node(
+    mut events: EventReader<ChooseMapNodeEvent>,
     mut progression: ResMut<MetagameProgression>,
     mut next_state: ResMut<NextState<AppState>>,
     mut current_level_config: ResMut<CurrentLevelConfig>,
+    q_player: Query<(Entity, &Health), With<Player>>,
+    mut heal_writer: EventWriter<HealEvent>,
+    mut damage_writer: EventWriter<TryDamageEvent>,
+    mut money: ResMut<Money>,
+    mut profile: ResMut<Profile>,
+    mut run_rng: ResMut<RunRng>,
     mut commands: Commands,
 ) {
-    match &progression.levels[progression.current_step_index] {
-        // This match is duplicated with `on_finished_step`!
-        GameStep::Level(level_config) => {
+    // Only the first choice counts - the rest were made on a map that's already outdated
+    let Some(&ChooseMapNodeEvent { node }) = events.read().next() else {
+        return;
+    };
+    events.clear();
+    if !progression.available_nodes().contains(&node) {
+        return;
+    }
+    progression.path.push(node);
+    progression.last_event = None;
+    run_rng.start_step(progression.path.len());
+    let layer = progression.path.len() - 1;
+
+    let step = progression.map.node(layer, node).step.clone();
+    match &step {
+        GameStep::Level(level_config) | GameStep::EliteLevel(level_config) => {
             current_level_config.0 = level_config.clone();
             next_state.set(AppState::Defending);
 
             progression.current_level += 1;
+            commands.queue(ScaleDifficulty {
+                level: progression.current_level,
+            });
         }
         GameStep::AbilityShop => {
             // TODO: Here I'm using an event and let the triggered function change the state,
@@ -91,43 +187,89 @@ fn start_game(
                 shop_type: ShopType::Upgrades,
             });
         }
+        GameStep::ItemShop => {
+            commands.trigger(EnterLevelTransitionEvent {
+                shop_type: ShopType::Items,
+            });
+        }
+        GameStep::Rest => {
+            for (player, health) in q_player.iter() {
+                heal_writer.write(HealEvent {
+                    target_entity: player,
+                    amount: health.max_health * REST_HEALING,
+                });
+            }
+            progression.last_event = Some("You rest for a while.".into());
+        }
+        GameStep::Event => {
+            let outcome = match run_rng.rng().gen_range(0..3) {
+                0 => {
+                    money.0 += EVENT_MONEY;
+                    profile.earn_money(EVENT_MONEY);
+                    "You find a pouch of coins."
+                }
+                1 => {
+                    for (player, health) in q_player.iter() {
+                        heal_writer.write(HealEvent {
+                            target_entity: player,
+                            amount: health.max_health - health.health,
+                        });
+                    }
+                    "A fairy heals your wounds."
+                }
+                _ => {
+                    for (player, health) in q_player.iter() {
+                        damage_writer.write(TryDamageEvent {
+                            target_entity: player,
+                            damage: TRAP_DAMAGE.min(health.health - 1.).max(0.),
+                            damage_type: DamageType::Other,
+                            source: None,
+                        });
+                    }
+                    "It was a trap!"
+                }
+            };
+            progression.last_event = Some(outcome.into());
+        }
+    }
+
+    // The other steps end with `on_finished_step`, but these happen right on the map
+    if progression.is_finished() && matches!(step, GameStep::Rest | GameStep::Event) {
+        next_state.set(AppState::Victory);
     }
 }
 
 fn on_finished_step(
     mut progression: ResMut<MetagameProgression>,
     mut next_state: ResMut<NextState<AppState>>,
-    mut current_level_config: ResMut<CurrentLevelConfig>,
-    mut commands: Commands,
+    mut money: ResMut<Money>,
+    mut profile: ResMut<Profile>,
+    mut inventory: ResMut<Inventory>,
+    mut run_rng: ResMut<RunRng>,
 ) {
-    if progression.current_step_index == progression.levels.len() - 1 {
-        println!("You win!");
-        next_state.set(AppState::Victory);
-        return;
+    let (reward, item_chance) = match progression.current_node().map(|node| &node.step) {
+        Some(GameStep::Level(_)) => (LEVEL_REWARD, LEVEL_ITEM_CHANCE),
+        Some(GameStep::EliteLevel(_)) => (ELITE_REWARD, 1.),
+        _ => (0., 0.),
+    };
+    if reward > 0. {
+        money.0 += reward;
+        profile.earn_money(reward);
+    }
+    if item_chance > 0. && run_rng.rng().gen_bool(item_chance) {
+        let item = *Item::ALL.choose(run_rng.rng()).unwrap();
+        let found = if inventory.add(item) {
+            format!("You found a {}!", item.name())
+        } else {
+            format!("You found a {}, but had no room for it.", item.name())
+        };
+        progression.last_event = Some(found);
     }
 
-    progression.current_step_index += 1;
-    println!("Transitioned to step {}", progression.current_step_index);
-
-    match &progression.levels[progression.current_step_index] {
-        GameStep::Level(level_config) => {
-            current_level_config.0 = level_config.clone();
-            next_state.set(AppState::Defending);
-
-            progression.current_level += 1;
-        }
-        GameStep::AbilityShop => {
-            // TODO: Here I'm using an event and let the triggered function change the state,
-            // as opposed to GameStep::Level, where I change the state here.
-            // Which is better?
-            commands.trigger(EnterLevelTransitionEvent {
-                shop_type: ShopType::Abilities,
-            });
-        }
-        GameStep::UpgradeShop => {
-            commands.trigger(EnterLevelTransitionEvent {
-                shop_type: ShopType::Upgrades,
-            });
-        }
+    if progression.is_finished() {
+        println!("You win!");
+        next_state.set(AppState::Victory);
+    } else {
+        next_state.set(AppState::ActionMenu);
     }
 }
@@ -0,0 +1,173 @@
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bullet_hell::{AbilityUpgradePool, ParryEvent, ParryKind},
+    ui::level_transition::ShopType,
+    upgrades::{GlobalUpgrade, UpgradeRegistry},
+    utils::resources::SelectionsPool,
+    AppState,
+};
+
+use super::plugin::MetagameProgression;
+
+const PROFILE_PATH: &str = "profile.ron";
+
+pub fn plugin(app: &mut App) {
+    app.insert_resource(load_profile())
+        .add_systems(OnEnter(AppState::ActionMenu), save_profile)
+        .add_systems(
+            OnEnter(AppState::Defeat),
+            (record_run_end(false), save_profile).chain(),
+        )
+        .add_systems(
+            OnEnter(AppState::Victory),
+            (record_run_end(true), save_profile).chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                count_parries.run_if(in_state(AppState::Defending)),
+                record_best_level.run_if(resource_changed::<MetagameProgression>),
+                // Also runs on the first frame, to fill the pools with what's unlocked already
+                (check_unlocks, fill_unlocked_pools)
+                    .chain()
+                    .run_if(resource_changed::<Profile>),
+            ),
+        );
+}
+
+/// What it takes to unlock an upgrade.
+enum Achievement {
+    WinRuns(u32),
+    ParryBullets(u32),
+    ReachLevel(usize),
+    EarnMoney(f32),
+}
+
+impl Achievement {
+    fn is_met(&self, profile: &Profile) -> bool {
+        match self {
+            Self::WinRuns(runs) => profile.runs_won >= *runs,
+            Self::ParryBullets(parries) => profile.total_parries >= *parries,
+            Self::ReachLevel(level) => profile.best_level >= *level,
+            Self::EarnMoney(money) => profile.total_money_earned >= *money,
+        }
+    }
+}
+
+/// Upgrades (and abilities) that start locked, by id. Everything else is unlocked from the start.
+const LOCKED_UPGRADES: [(&str, Achievement); 4] = [
+    ("battering_ram", Achievement::WinRuns(1)),
+    ("yellow_boxes", Achievement::ReachLevel(3)),
+    ("cool_shoes", Achievement::ParryBullets(100)),
+    ("apple", Achievement::EarnMoney(200.)),
+];
+
+/// Progress that's kept between runs.
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Profile {
+    pub runs_played: u32,
+    pub runs_won: u32,
+    pub best_level: usize,
+    pub total_money_earned: f32,
+    pub total_parries: u32,
+    /// The ids of the locked upgrades that were unlocked so far.
+    pub unlocked: Vec<String>,
+}
+
+impl Profile {
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        let locked = LOCKED_UPGRADES
+            .iter()
+            .any(|(locked_id, _)| *locked_id == id);
+        !locked || self.unlocked.iter().any(|unlocked| unlocked == id)
+    }
+
+    pub fn earn_money(&mut self, amount: f32) {
+        self.total_money_earned += amount;
+    }
+}
+
+fn load_profile() -> Profile {
+    let Ok(profile_string) = fs::read_to_string(PROFILE_PATH) else {
+        // No profile yet - this is the first time the game is played
+        return Profile::default();
+    };
+    ron::de::from_str(&profile_string).unwrap_or_else(|e| {
+        error!("Failed to load the profile, starting a new one: {}", e);
+        Profile::default()
+    })
+}
+
+fn save_profile(profile: Res<Profile>) {
+    let result = ron::ser::to_string_pretty(&*profile, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|profile_string| {
+            fs::write(PROFILE_PATH, profile_string).map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        error!("Failed to save the profile: {}", e);
+    }
+}
+
+fn record_run_end(won: bool) -> impl Fn(ResMut<Profile>) {
+    move |mut profile: ResMut<Profile>| {
+        profile.runs_played += 1;
+        if won {
+            profile.runs_won += 1;
+        }
+    }
+}
+
+fn record_best_level(progression: Res<MetagameProgression>, mut profile: ResMut<Profile>) {
+    if progression.current_level > profile.best_level {
+        profile.best_level = progression.current_level;
+    }
+}
+
+fn count_parries(mut parry_events: EventReader<ParryEvent>, mut profile: ResMut<Profile>) {
+    let parries = parry_events
+        .read()
+        .filter(|event| event.kind != ParryKind::Blocked)
+        .count() as u32;
+    if parries > 0 {
+        profile.total_parries += parries;
+    }
+}
+
+fn check_unlocks(mut profile: ResMut<Profile>) {
+    for (id, achievement) in LOCKED_UPGRADES.iter() {
+        if !profile.is_unlocked(id) && achievement.is_met(&profile) {
+            info!("Unlocked {}", id);
+            profile.unlocked.push(id.to_string());
+        }
+    }
+}
+
+/// Fills the shops with the unlocked upgrades. The pools are rebuilt rather than added to, so
+/// that they're in the registry's order no matter when the upgrades got unlocked - the shops pick
+/// by position, and the same seed should always offer the same upgrades.
+fn fill_unlocked_pools(
+    profile: Res<Profile>,
+    registry: Res<UpgradeRegistry>,
+    mut upgrade_pool: ResMut<SelectionsPool<GlobalUpgrade>>,
+    mut ability_pool: ResMut<AbilityUpgradePool>,
+) {
+    *upgrade_pool = SelectionsPool::default();
+    ability_pool.0 = SelectionsPool::default();
+    for (upgrade, shop) in registry.iter_sold() {
+        if !profile.is_unlocked(upgrade.upgrade.id) {
+            continue;
+        }
+        match shop {
+            ShopType::Upgrades => upgrade_pool.add_item(upgrade.clone()),
+            ShopType::Abilities => ability_pool.0.add_item(upgrade.clone()),
+            // Items aren't upgrades, and the item shop always has all of them
+            ShopType::Items => {}
+        }
+    }
+}
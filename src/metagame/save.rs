@@ -0,0 +1,284 @@
+use std::{fs, path::Path};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bullet_hell::{
+        Boss, DifficultyLevel, Enemy, EnemySpawn, Health, Inventory, Item, Player, ReviveUsed,
+        SpawnEnemy,
+    },
+    upgrades::{AppliedGlobalUpgrades, UpgradeApplier, UpgradeRegistry},
+    utils::resources::RunRng,
+    AppState, Money, RewindRunEvent,
+};
+
+use super::plugin::MetagameProgression;
+
+const SAVE_PATH: &str = "save.ron";
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<ContinueRunEvent>()
+        .add_event::<RestartLevelEvent>()
+        .init_resource::<LevelCheckpoint>()
+        .add_systems(
+            OnEnter(AppState::ActionMenu),
+            (save_run, resume_restarted_level),
+        )
+        .add_systems(OnEnter(AppState::Defending), take_checkpoint)
+        .add_systems(OnEnter(AppState::Defeat), delete_save)
+        .add_systems(OnEnter(AppState::Victory), delete_save)
+        .add_systems(
+            Update,
+            (
+                load_run.run_if(on_event::<ContinueRunEvent>),
+                restart_level.run_if(on_event::<RestartLevelEvent>),
+                // Rest sites and events change the run without leaving the map, and their heals
+                // and traps only land after the progression changed
+                save_run.run_if(
+                    in_state(AppState::ActionMenu).and(
+                        resource_changed::<MetagameProgression>
+                            .or(resource_changed::<Inventory>)
+                            .or(player_health_changed),
+                    ),
+                ),
+            ),
+        );
+}
+
+/// Resumes the run from the save file.
+#[derive(Event)]
+pub struct ContinueRunEvent;
+
+/// Plays the current level again, from the way the run was when it started.
+#[derive(Event)]
+pub struct RestartLevelEvent;
+
+/// The run as it was when the current level started.
+#[derive(Resource, Default)]
+struct LevelCheckpoint(Option<RunSave>);
+
+/// The level is being restarted, and the map is only passed through on the way back to it.
+#[derive(Resource)]
+struct RestartingLevel;
+
+/// Everything about a run in progress, saved at every visit to the map.
+/// The player's ability isn't saved on its own, since re-applying the upgrades gives it back.
+#[derive(Serialize, Deserialize)]
+struct RunSave {
+    path: Vec<usize>,
+    current_level: usize,
+    upgrades: Vec<String>,
+    health: f32,
+    max_health: f32,
+    money: f32,
+    #[serde(default)]
+    items: Vec<Item>,
+    /// Saves from before the enemies were saved keep the ones a run starts with.
+    #[serde(default)]
+    enemies: Option<Vec<SavedEnemy>>,
+    /// The randomness picks up from here and the path, see `RunRng`.
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    revive_used: bool,
+}
+
+/// An enemy the run has accumulated. Bosses aren't saved, since their levels spawn them.
+#[derive(Serialize, Deserialize)]
+struct SavedEnemy {
+    id: String,
+    position: Vec2,
+    facing: Vec2,
+}
+
+/// Everything `RunSave` is made of.
+#[derive(SystemParam)]
+struct RunState<'w, 's> {
+    progression: Res<'w, MetagameProgression>,
+    applied_upgrades: Res<'w, AppliedGlobalUpgrades>,
+    q_player: Query<'w, 's, &'static Health, With<Player>>,
+    money: Res<'w, Money>,
+    inventory: Res<'w, Inventory>,
+    q_enemies: Query<'w, 's, &'static EnemySpawn, Without<Boss>>,
+    run_rng: Res<'w, RunRng>,
+    revive_used: Res<'w, ReviveUsed>,
+}
+
+impl RunState<'_, '_> {
+    fn to_save(&self) -> Option<RunSave> {
+        let health = self.q_player.single().ok()?;
+        Some(RunSave {
+            path: self.progression.path.clone(),
+            current_level: self.progression.current_level,
+            upgrades: self
+                .applied_upgrades
+                .applied_upgrades
+                .iter()
+                .map(|upgrade| upgrade.upgrade.id.to_string())
+                .collect(),
+            health: health.health,
+            max_health: health.max_health,
+            money: self.money.0,
+            items: self.inventory.items().to_vec(),
+            enemies: Some(
+                self.q_enemies
+                    .iter()
+                    .map(|enemy| SavedEnemy {
+                        id: enemy.id.clone(),
+                        position: enemy.position,
+                        facing: enemy.facing,
+                    })
+                    .collect(),
+            ),
+            seed: Some(self.run_rng.seed()),
+            revive_used: self.revive_used.0,
+        })
+    }
+}
+
+fn player_health_changed(q_player: Query<(), (With<Player>, Changed<Health>)>) -> bool {
+    !q_player.is_empty()
+}
+
+pub fn save_exists() -> bool {
+    Path::new(SAVE_PATH).exists()
+}
+
+fn save_run(run_state: RunState, restarting: Option<Res<RestartingLevel>>) {
+    // Don't overwrite the last run's save before the player decided whether to continue it
+    if run_state.progression.path.is_empty() {
+        return;
+    }
+    // The restored run already has the level on its path, and continuing it would skip the level
+    if restarting.is_some() {
+        return;
+    }
+    let Some(save) = run_state.to_save() else {
+        return;
+    };
+    let result = ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|save_string| fs::write(SAVE_PATH, save_string).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        error!("Failed to save the run: {}", e);
+    }
+}
+
+fn delete_save() {
+    if save_exists() {
+        if let Err(e) = fs::remove_file(SAVE_PATH) {
+            error!("Failed to delete the save: {}", e);
+        }
+    }
+}
+
+fn read_save() -> Result<RunSave, String> {
+    let save_string = fs::read_to_string(SAVE_PATH).map_err(|e| e.to_string())?;
+    ron::de::from_str(&save_string).map_err(|e| e.to_string())
+}
+
+fn load_run(world: &mut World) {
+    let save = match read_save() {
+        Ok(save) => save,
+        Err(e) => {
+            error!("Failed to load the save: {}", e);
+            return;
+        }
+    };
+
+    let progression = world.resource::<MetagameProgression>();
+    if !progression.path.is_empty() {
+        warn!("Tried to continue a run in the middle of another one");
+        return;
+    }
+    if !progression.map.is_valid_path(&save.path) {
+        error!("The saved run doesn't fit the map, ignoring it");
+        return;
+    }
+    apply_save(world, save);
+}
+
+fn apply_save(world: &mut World, save: RunSave) {
+    // Before the upgrades, so that a used up revive isn't given back
+    world.resource_mut::<ReviveUsed>().0 = save.revive_used;
+    let apply_upgrade = world.resource::<UpgradeApplier>().apply_upgrade_to_all;
+    for id in save.upgrades.iter() {
+        let Some(upgrade) = world.resource::<UpgradeRegistry>().get(id).cloned() else {
+            warn!("The save has an unknown upgrade: {}", id);
+            continue;
+        };
+        if let Err(e) = world.run_system_with(apply_upgrade, upgrade) {
+            error!("Failed to apply the upgrade {}: {}", id, e);
+        }
+    }
+
+    // After the upgrades, since some of them change the health too
+    let mut q_player = world.query_filtered::<&mut Health, With<Player>>();
+    for mut health in q_player.iter_mut(world) {
+        health.health = save.health;
+        health.max_health = save.max_health;
+    }
+    world.resource_mut::<Money>().0 = save.money;
+    let mut inventory = world.resource_mut::<Inventory>();
+    for item in save.items {
+        inventory.add(item);
+    }
+
+    if let Some(enemies) = save.enemies {
+        let mut q_enemies = world.query_filtered::<Entity, With<Enemy>>();
+        let spawned: Vec<_> = q_enemies.iter(world).collect();
+        for entity in spawned {
+            world.despawn(entity);
+        }
+        for enemy in enemies {
+            SpawnEnemy::new(enemy.id, enemy.position, enemy.facing).apply(world);
+        }
+    }
+    if let Some(seed) = save.seed {
+        world.insert_resource(RunRng::at_step(seed, save.path.len()));
+    }
+
+    let mut progression = world.resource_mut::<MetagameProgression>();
+    progression.path = save.path;
+    progression.current_level = save.current_level;
+    // Set by the difficulty scaling whenever a level is picked, which the save skips over
+    world.resource_mut::<DifficultyLevel>().0 = save.current_level;
+}
+
+fn take_checkpoint(
+    run_state: RunState,
+    mut checkpoint: ResMut<LevelCheckpoint>,
+    mut commands: Commands,
+) {
+    commands.remove_resource::<RestartingLevel>();
+    if let Some(save) = run_state.to_save() {
+        checkpoint.0 = Some(save);
+    }
+}
+
+/// Rewinds the run and brings it back to the checkpoint. The run's stats carry on, with the
+/// abandoned attempt among the levels.
+fn restart_level(world: &mut World) {
+    let Some(checkpoint) = world.resource_mut::<LevelCheckpoint>().0.take() else {
+        warn!("There's no checkpoint to restart the level from");
+        return;
+    };
+    world.trigger(RewindRunEvent);
+    world.flush();
+    apply_save(world, checkpoint);
+    world.insert_resource(RestartingLevel);
+    // Entering the fight again is what starts the level over
+    world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::ActionMenu);
+}
+
+fn resume_restarted_level(
+    restarting: Option<Res<RestartingLevel>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if restarting.is_some() {
+        next_state.set(AppState::Defending);
+    }
+}
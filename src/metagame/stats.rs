@@ -0,0 +1,263 @@
+use std::{collections::BTreeMap, fs};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    bullet_hell::{
+        DamageOccurredEvent, DashStartedEvent, LevelFinishedEvent, ParryEvent, ParryKind, Player,
+    },
+    game_config::GameConfig,
+    ui::{self, run_end_menu::RunEndPopup},
+    upgrades::{AppliedGlobalUpgrades, UpgradeRegistry},
+    utils::resources::RunRng,
+    AppState, ResetRunEvent,
+};
+
+use super::plugin::MetagameProgression;
+
+const SUMMARY_PATH: &str = "run_summary.ron";
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<RunStats>()
+        .add_observer(reset_stats)
+        .add_systems(OnEnter(AppState::Defending), start_level)
+        .add_systems(OnEnter(AppState::Defeat), export_summary)
+        .add_systems(OnEnter(AppState::Victory), export_summary)
+        .add_systems(
+            Update,
+            (
+                (time_level, count_damage, count_parries, count_dashes)
+                    .run_if(in_state(AppState::Defending)),
+                finish_level.run_if(on_event::<LevelFinishedEvent>),
+                spawn_summary,
+            ),
+        );
+}
+
+#[derive(Serialize)]
+struct LevelStats {
+    name: &'static str,
+    seconds: f32,
+    completed: bool,
+}
+
+/// What happened during the current run, shown when it ends.
+#[derive(Resource, Serialize, Default)]
+struct RunStats {
+    levels: Vec<LevelStats>,
+    /// The damage the player took, by the name of whoever dealt it.
+    damage_taken: BTreeMap<String, f32>,
+    /// The same damage, by its type.
+    damage_taken_by_type: BTreeMap<&'static str, f32>,
+    /// What last hurt the player, which is what killed them if they died.
+    last_hit: Option<String>,
+    damage_dealt: f32,
+    parries: u32,
+    dashes: u32,
+}
+
+/// The stats, together with what's only known when the run ends.
+#[derive(Serialize)]
+struct RunSummary<'a> {
+    seed: u64,
+    upgrades: Vec<&'static str>,
+    #[serde(flatten)]
+    stats: &'a RunStats,
+}
+
+fn reset_stats(_trigger: Trigger<ResetRunEvent>, mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+fn start_level(progression: Res<MetagameProgression>, mut stats: ResMut<RunStats>) {
+    let name = progression
+        .current_node()
+        .map_or("Level", |node| node.step.label());
+    stats.levels.push(LevelStats {
+        name,
+        seconds: 0.,
+        completed: false,
+    });
+}
+
+fn time_level(time: Res<Time>, mut stats: ResMut<RunStats>) {
+    if let Some(level) = stats.levels.last_mut() {
+        level.seconds += time.delta_secs();
+    }
+}
+
+fn finish_level(mut stats: ResMut<RunStats>) {
+    if let Some(level) = stats.levels.last_mut() {
+        level.completed = true;
+    }
+}
+
+fn count_damage(
+    mut damage_events: EventReader<DamageOccurredEvent>,
+    q_players: Query<(), With<Player>>,
+    q_names: Query<&Name>,
+    mut stats: ResMut<RunStats>,
+) {
+    for event in damage_events.read() {
+        if q_players.contains(event.target_entity) {
+            // The source might be gone already, like a cannon that died after shooting
+            let source = event
+                .source
+                .and_then(|source| q_names.get(source).ok())
+                .map_or("Unknown", |name| name.as_str());
+            *stats.damage_taken.entry(source.to_string()).or_default() += event.damage;
+            *stats
+                .damage_taken_by_type
+                .entry(event.damage_type.name())
+                .or_default() += event.damage;
+            if event.damage > 0. {
+                stats.last_hit = Some(format!("{} ({})", source, event.damage_type.name()));
+            }
+        } else if event
+            .source
+            .is_some_and(|source| q_players.contains(source))
+        {
+            stats.damage_dealt += event.damage;
+        }
+    }
+}
+
+fn count_parries(mut parry_events: EventReader<ParryEvent>, mut stats: ResMut<RunStats>) {
+    stats.parries += parry_events
+        .read()
+        .filter(|event| event.kind != ParryKind::Blocked)
+        .count() as u32;
+}
+
+fn count_dashes(mut dash_events: EventReader<DashStartedEvent>, mut stats: ResMut<RunStats>) {
+    stats.dashes += dash_events.read().count() as u32;
+}
+
+fn export_summary(
+    config: Res<GameConfig>,
+    stats: Res<RunStats>,
+    applied_upgrades: Res<AppliedGlobalUpgrades>,
+    registry: Res<UpgradeRegistry>,
+    run_rng: Res<RunRng>,
+) {
+    if !config.export_run_summary {
+        return;
+    }
+    let summary = RunSummary {
+        seed: run_rng.seed(),
+        upgrades: applied_upgrades
+            .applied_upgrades
+            .iter()
+            .map(|upgrade| upgrade.upgrade.id)
+            .filter(|id| !registry.is_hidden(id))
+            .collect(),
+        stats: &stats,
+    };
+    let result = ron::ser::to_string_pretty(&summary, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|summary_string| {
+            fs::write(SUMMARY_PATH, summary_string).map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        error!("Failed to export the run summary: {}", e);
+    }
+}
+
+fn summary_text(stats: &RunStats, seed: u64) -> String {
+    let mut lines = Vec::new();
+    for (i, level) in stats.levels.iter().enumerate() {
+        let result = if level.completed { "" } else { " (died)" };
+        lines.push(format!(
+            "{}. {}: {:.0}s{}",
+            i + 1,
+            level.name,
+            level.seconds,
+            result
+        ));
+    }
+    let died = stats.levels.last().is_some_and(|level| !level.completed);
+    if let (true, Some(last_hit)) = (died, &stats.last_hit) {
+        lines.push(format!("Killed by: {last_hit}"));
+    }
+    if stats.damage_taken.is_empty() {
+        lines.push("Took no damage".into());
+    }
+    for (source, damage) in stats.damage_taken.iter() {
+        lines.push(format!("Damage from {source}: {damage:.0}"));
+    }
+    if !stats.damage_taken_by_type.is_empty() {
+        let by_type: Vec<String> = stats
+            .damage_taken_by_type
+            .iter()
+            .map(|(damage_type, damage)| format!("{damage_type}: {damage:.0}"))
+            .collect();
+        lines.push(by_type.join("  "));
+    }
+    lines.push(format!(
+        "Damage dealt: {:.0}  Parries: {}  Dashes: {}",
+        stats.damage_dealt, stats.parries, stats.dashes
+    ));
+    lines.push(format!("Seed: {seed}"));
+    lines.join("\n")
+}
+
+/// Adds the summary between the title and the buttons of the defeat/victory popup.
+fn spawn_summary(
+    q_popup: Query<Entity, Added<RunEndPopup>>,
+    stats: Res<RunStats>,
+    applied_upgrades: Res<AppliedGlobalUpgrades>,
+    registry: Res<UpgradeRegistry>,
+    run_rng: Res<RunRng>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    for popup in q_popup.iter() {
+        let summary = commands
+            .spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.),
+                    ..default()
+                },
+                Name::new("Run Summary"),
+                children![(
+                    Text(summary_text(&stats, run_rng.seed())),
+                    TextFont {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextLayout::new_with_justify(JustifyText::Center),
+                )],
+            ))
+            .with_children(|builder| {
+                builder
+                    .spawn(Node {
+                        column_gap: Val::Px(5.),
+                        ..default()
+                    })
+                    .with_children(|builder| {
+                        // Not the ones the game applied by itself, like the difficulty
+                        let picked = applied_upgrades
+                            .applied_upgrades
+                            .iter()
+                            .filter(|upgrade| !registry.is_hidden(upgrade.upgrade.id));
+                        for upgrade in picked {
+                            builder.spawn((
+                                Node {
+                                    width: Val::Px(32.),
+                                    height: Val::Px(32.),
+                                    ..default()
+                                },
+                                ImageNode::new(asset_server.load(upgrade.upgrade.icon_texture)),
+                                BackgroundColor(ui::palette::WHITE),
+                                Name::new(upgrade.upgrade.name),
+                            ));
+                        }
+                    });
+            })
+            .id();
+        commands.entity(popup).insert_children(1, &[summary]);
+    }
+}
@@ -1,69 +1,102 @@
-use std::cmp::Ordering;
-
 use bevy::prelude::*;
-use bevy_inspector_egui::prelude::*;
 
-use crate::ui;
+use crate::{ui, Money};
 
-use super::plugin::{GameStep, MetagameProgression};
+use super::plugin::MetagameProgression;
 
-#[derive(Component, InspectorOptions, Default, Reflect)]
-#[reflect(Component, InspectorOptions)]
-pub struct LevelText(usize);
+#[derive(Component)]
+struct MapUI;
 
-fn steps_to_strings<'a, T: Iterator<Item = &'a GameStep>>(game_steps: T) -> Vec<String> {
-    let mut level_index = 0;
+/// Visited nodes are green, the choices for the next step are white, and nodes that can still be
+/// reached later are gray. Everything else is dark gray.
+fn node_color(progression: &MetagameProgression, layer: usize, index: usize) -> Color {
+    if progression.path.get(layer) == Some(&index) {
+        return ui::palette::GREEN;
+    }
+    if layer < progression.path.len() {
+        return ui::palette::DARK_GRAY;
+    }
+    if layer == progression.path.len() {
+        return if progression.available_nodes().contains(&index) {
+            ui::palette::WHITE
+        } else {
+            ui::palette::DARK_GRAY
+        };
+    }
 
-    game_steps
-        .map(|step| match step {
-            GameStep::Level(_) => {
-                level_index += 1;
-                format!("Level {level_index}")
-            }
-            GameStep::AbilityShop => "Ability Shop".into(),
-            GameStep::UpgradeShop => "Shop".into(),
-        })
-        .collect()
+    let mut reachable = progression.available_nodes();
+    for current_layer in progression.path.len()..layer {
+        reachable = reachable
+            .iter()
+            .flat_map(|node| progression.map.node(current_layer, *node).next.clone())
+            .collect();
+    }
+    if reachable.contains(&index) {
+        ui::palette::GRAY
+    } else {
+        ui::palette::DARK_GRAY
+    }
 }
 
-pub fn spawn_menu(mut commands: Commands, progress: Res<MetagameProgression>) {
+pub fn spawn_map(mut commands: Commands, progression: Res<MetagameProgression>, money: Res<Money>) {
     commands
-        // .ui_builder(UiRoot)
-        .spawn(Node {
-            position_type: PositionType::Absolute,
-            flex_direction: FlexDirection::Column,
-            top: Val::Percent(50.),
-            bottom: Val::Percent(50.),
-            left: Val::Px(25.),
-            ..Default::default()
-        })
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                row_gap: Val::Px(10.),
+                top: Val::Percent(10.),
+                left: Val::Px(25.),
+                ..Default::default()
+            },
+            MapUI,
+            Name::new("Run Map"),
+        ))
         .with_children(|builder| {
-            for (i, name) in steps_to_strings(progress.iter_levels()).iter().enumerate() {
-                builder.spawn(Node::default()).with_children(|builder| {
-                    builder.spawn((
-                        Text(name.into()),
-                        TextFont {
-                            font_size: 32.0,
-                            ..default()
-                        },
-                        LevelText(i),
-                    ));
-                });
+            for (layer, nodes) in progression.map.layers.iter().enumerate() {
+                builder
+                    .spawn(Node {
+                        column_gap: Val::Px(30.),
+                        ..default()
+                    })
+                    .with_children(|builder| {
+                        for (index, node) in nodes.iter().enumerate() {
+                            builder.spawn((
+                                Text(node.step.label().into()),
+                                TextFont {
+                                    font_size: 32.0,
+                                    ..default()
+                                },
+                                TextColor(node_color(&progression, layer, index)),
+                            ));
+                        }
+                    });
             }
         });
+
+    let mut status = format!("Money: {:.0}", money.0);
+    if let Some(last_event) = &progression.last_event {
+        status = format!("{last_event}\n{status}");
+    }
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(10.),
+            right: Val::Px(25.),
+            ..default()
+        },
+        Text(status),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        MapUI,
+        Name::new("Run Map Status"),
+    ));
 }
 
-pub fn update_text_on_level_transition(
-    mut query: Query<(&mut TextColor, &LevelText)>,
-    progress: Res<MetagameProgression>,
-) {
-    if progress.is_changed() {
-        for (mut color, level) in query.iter_mut() {
-            match level.0.cmp(&progress.current_step_index) {
-                Ordering::Less => *color = ui::palette::BLACK.into(),
-                Ordering::Equal => *color = ui::palette::GREEN.into(),
-                Ordering::Greater => *color = ui::palette::WHITE.into(),
-            }
-        }
+pub fn despawn_map(mut commands: Commands, q_map: Query<Entity, With<MapUI>>) {
+    for entity in q_map.iter() {
+        commands.entity(entity).despawn();
     }
 }
@@ -0,0 +1,194 @@
+use bevy::{ecs::system::SystemId, prelude::*};
+
+use crate::{
+    bullet_hell::{Inventory, UseItemEvent},
+    utils::{
+        data_structures::Index,
+        menu_system::{MenuStack, MultiChoiceButton, MultiChoiceParent, SpawnedMenu},
+        z_index,
+    },
+    AppState,
+};
+
+use super::palette;
+
+/// The ITEM menu, opened from the action menu, for using items between fights.
+pub struct ItemMenuPlugin;
+
+impl Plugin for ItemMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ItemMenuSystems>()
+            .add_event::<OpenItemMenuEvent>()
+            .add_systems(
+                Update,
+                spawn_item_menu
+                    .run_if(in_state(AppState::ActionMenu).and(on_event::<OpenItemMenuEvent>)),
+            )
+            .add_systems(OnExit(AppState::ActionMenu), close_item_menu);
+    }
+}
+
+#[derive(Event)]
+pub struct OpenItemMenuEvent;
+
+#[derive(Resource)]
+struct ItemMenuSystems {
+    activate: SystemId<In<Entity>>,
+    deactivate: SystemId<In<Entity>>,
+    use_item: SystemId<In<Entity>>,
+    back: SystemId<In<Entity>>,
+}
+
+impl FromWorld for ItemMenuSystems {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            activate: world.register_system(activate),
+            deactivate: world.register_system(deactivate),
+            use_item: world.register_system(use_item),
+            back: world.register_system(back),
+        }
+    }
+}
+
+#[derive(Component)]
+struct ItemMenu;
+
+/// The inventory slot this button uses.
+#[derive(Component)]
+struct ItemChoice(usize);
+
+fn spawn_item_menu(
+    mut events: EventReader<OpenItemMenuEvent>,
+    q_menu: Query<(), With<ItemMenu>>,
+    inventory: Res<Inventory>,
+    systems: Res<ItemMenuSystems>,
+    mut menu_stack: ResMut<MenuStack>,
+    mut spawned_menu: EventWriter<SpawnedMenu>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    events.clear();
+    if !q_menu.is_empty() {
+        return;
+    }
+    // Bombs and the like are kept for the fights
+    let usable: Vec<_> = inventory
+        .items()
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.usable_outside_fights())
+        .collect();
+    let button_node = Node {
+        border: UiRect::all(Val::Px(5.)),
+        padding: UiRect::all(Val::Px(5.)),
+        column_gap: Val::Px(10.),
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text = |text: &str| {
+        (
+            Text(text.into()),
+            TextFont {
+                font_size: 32.0,
+                ..default()
+            },
+        )
+    };
+    let menu = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(20.),
+                bottom: Val::Percent(15.),
+                width: Val::Percent(60.),
+                padding: UiRect::all(Val::Px(10.)),
+                justify_content: JustifyContent::SpaceAround,
+                ..default()
+            },
+            z_index::POPUP_MENU,
+            BackgroundColor(palette::DARK_GRAY),
+            MultiChoiceParent {
+                selected: Index::new(usable.len() + 1, 0),
+            },
+            ItemMenu,
+            Name::new("Item Menu"),
+        ))
+        .with_children(|builder| {
+            for (slot, item) in usable {
+                builder
+                    .spawn((
+                        button_node.clone(),
+                        BorderColor(palette::BLACK),
+                        MultiChoiceButton {
+                            on_selected: Some(systems.use_item),
+                            activate: systems.activate,
+                            deactivate: systems.deactivate,
+                        },
+                        ItemChoice(slot),
+                    ))
+                    .with_children(|builder| {
+                        builder.spawn((
+                            Node {
+                                width: Val::Px(32.),
+                                height: Val::Px(32.),
+                                ..default()
+                            },
+                            ImageNode::new(asset_server.load(item.icon_texture())),
+                        ));
+                        builder.spawn(button_text(item.name()));
+                    });
+            }
+            builder
+                .spawn((
+                    button_node.clone(),
+                    BorderColor(palette::BLACK),
+                    MultiChoiceButton {
+                        on_selected: Some(systems.back),
+                        activate: systems.activate,
+                        deactivate: systems.deactivate,
+                    },
+                ))
+                .with_child(button_text("Back"));
+        })
+        .id();
+    menu_stack.push_menu(menu);
+    spawned_menu.write(SpawnedMenu(menu));
+}
+
+fn close_item_menu(
+    q_menu: Query<Entity, With<ItemMenu>>,
+    mut menu_stack: ResMut<MenuStack>,
+    mut commands: Commands,
+) {
+    for menu in q_menu.iter() {
+        if menu_stack.get_current_menu() == Some(menu) {
+            menu_stack.pop_menu(menu);
+        }
+        commands.entity(menu).despawn();
+    }
+}
+
+fn deactivate(In(entity): In<Entity>, mut border_query: Query<&mut BorderColor>) {
+    border_query.get_mut(entity).unwrap().0 = palette::BLACK;
+}
+
+fn activate(In(entity): In<Entity>, mut border_query: Query<&mut BorderColor>) {
+    border_query.get_mut(entity).unwrap().0 = palette::GREEN;
+}
+
+fn use_item(
+    In(entity): In<Entity>,
+    q_choices: Query<&ItemChoice>,
+    mut use_item_writer: EventWriter<UseItemEvent>,
+    mut commands: Commands,
+) {
+    if let Ok(ItemChoice(slot)) = q_choices.get(entity) {
+        use_item_writer.write(UseItemEvent { slot: *slot });
+    }
+    // The slots change once the item is used, so the menu has to be opened again
+    commands.run_system_cached(close_item_menu);
+}
+
+fn back(In(_entity): In<Entity>, mut commands: Commands) {
+    commands.run_system_cached(close_item_menu);
+}
@@ -1,13 +1,17 @@
+use std::path::Path;
+
 use bevy::prelude::*;
 
 use crate::{
-    bullet_hell::AbilityUpgradePool,
+    bullet_hell::{AbilityUpgradePool, HealEvent, Health, Inventory, Item, Player},
     ui::{self, palette},
-    upgrades::{GlobalUpgrade, UpgradeApplier},
+    upgrades::{
+        AppliedGlobalUpgrades, GlobalUpgrade, SynergyRegistry, UpgradeApplier, UpgradeRegistry,
+    },
     utils::{
         data_structures::Index,
         menu_system::{MenuStack, MultiChoiceButton, MultiChoiceParent, SpawnedMenu},
-        resources::SelectionsPool,
+        resources::{RunRng, SelectionsPool},
         z_index,
     },
     AppState,
@@ -37,6 +41,7 @@ pub enum ShopType {
     #[default]
     Abilities,
     Upgrades,
+    Items,
 }
 
 #[derive(Event)]
@@ -56,7 +61,22 @@ struct UpgradeSelectMenuRoot;
 struct UpgradeSelectMenu;
 
 #[derive(Component)]
-struct UpgradeOption(Option<GlobalUpgrade>);
+struct UpgradeOption {
+    upgrade: Option<GlobalUpgrade>,
+    /// Comes together with the upgrade, and is applied with it.
+    curse: Option<GlobalUpgrade>,
+    /// Goes to the inventory, in the item shop.
+    item: Option<Item>,
+    /// Heals this fraction of the player's max health, instead of buying anything.
+    heal: Option<f32>,
+}
+
+/// What a card shows, whether it's selling an upgrade or an item.
+struct CardContent {
+    name: &'static str,
+    description: &'static str,
+    icon: Handle<Image>,
+}
 
 // TODO: defaults
 #[derive(Resource, Default)]
@@ -64,7 +84,8 @@ struct ShopParameters {
     shop_type: ShopType,
 }
 
-const MENU_ITEMS: [i32; 3] = [1, 2, 3];
+/// The fraction of the max health that resting in the shop heals.
+const SHOP_HEALING: f32 = 0.3;
 
 fn enter_store(
     trigger: Trigger<EnterLevelTransitionEvent>,
@@ -81,15 +102,16 @@ fn spawn_level_transition_menu(world: &mut World) {
     let pressed_system_id = world.register_system(process_upgrade_and_go_to_next_level);
 
     let shop_type = world.resource::<ShopParameters>().shop_type;
-    let upgrades = match shop_type {
+    let upgrades = world.resource_scope(|world, mut run_rng: Mut<RunRng>| match shop_type {
         ShopType::Abilities => world
             .resource::<AbilityUpgradePool>()
             .0
-            .get_multiple_random(3),
+            .get_multiple_random(3, run_rng.rng()),
         ShopType::Upgrades => world
             .resource::<SelectionsPool<GlobalUpgrade>>()
-            .get_multiple_random(3),
-    };
+            .get_multiple_random(3, run_rng.rng()),
+        ShopType::Items => Vec::new(),
+    });
     // TODO: Extract all of the logic ahead of here to a helper which receives the upgrades as parameters
     let asset_server = world.resource::<AssetServer>();
     let upgrades: Vec<Option<(GlobalUpgrade, Handle<Image>)>> = upgrades
@@ -101,6 +123,80 @@ fn spawn_level_transition_menu(world: &mut World) {
             })
         })
         .collect();
+    let registry = world.resource::<UpgradeRegistry>();
+    let curses: Vec<Option<GlobalUpgrade>> = upgrades
+        .iter()
+        .map(|upgrade| {
+            upgrade
+                .as_ref()
+                .and_then(|(upgrade, _)| registry.curse_of(upgrade.upgrade.id).cloned())
+        })
+        .collect();
+    let synergy_registry = world.resource::<SynergyRegistry>();
+    let applied_upgrades = world.resource::<AppliedGlobalUpgrades>();
+    // The names of the synergies each offer would complete
+    let synergies: Vec<Vec<&'static str>> = upgrades
+        .iter()
+        .map(|upgrade| match upgrade {
+            None => Vec::new(),
+            Some((upgrade, _)) => synergy_registry
+                .completed_by(upgrade.upgrade.id, applied_upgrades)
+                .map(|synergy| synergy.name)
+                .collect(),
+        })
+        .collect();
+
+    let mut cards: Vec<(Option<CardContent>, UpgradeOption, Vec<&'static str>)> = match shop_type {
+        ShopType::Items => Item::ALL
+            .into_iter()
+            .map(|item| {
+                let content = CardContent {
+                    name: item.name(),
+                    description: item.description(),
+                    icon: asset_server.load(item.icon_texture()),
+                };
+                let option = UpgradeOption {
+                    upgrade: None,
+                    curse: None,
+                    item: Some(item),
+                    heal: None,
+                };
+                (Some(content), option, Vec::new())
+            })
+            .collect(),
+        _ => upgrades
+            .into_iter()
+            .zip(curses)
+            .zip(synergies)
+            .map(|((upgrade, curse), synergies)| {
+                let content = upgrade.as_ref().map(|(upgrade, icon)| CardContent {
+                    name: upgrade.upgrade.name,
+                    description: upgrade.upgrade.description,
+                    icon: icon.clone(),
+                });
+                let option = UpgradeOption {
+                    upgrade: upgrade.map(|(upgrade, _)| upgrade),
+                    curse,
+                    item: None,
+                    heal: None,
+                };
+                (content, option, synergies)
+            })
+            .collect(),
+    };
+    // Every shop lets the player rest instead
+    let rest = CardContent {
+        name: "Rest",
+        description: "Heal 30% of your max HP instead",
+        icon: asset_server.load(Path::new("sprites/upgrades/apple.png")),
+    };
+    let rest_option = UpgradeOption {
+        upgrade: None,
+        curse: None,
+        item: None,
+        heal: Some(SHOP_HEALING),
+    };
+    cards.push((Some(rest), rest_option, Vec::new()));
 
     let outer_menu = world
         .spawn((
@@ -129,14 +225,20 @@ fn spawn_level_transition_menu(world: &mut World) {
             },
             BackgroundColor(ui::palette::BLACK),
             MultiChoiceParent {
-                selected: Index::new(MENU_ITEMS.len(), 0),
+                selected: Index::new(cards.len(), 0),
             },
             UpgradeSelectMenu,
             Name::new("UpgradeSelectMenu"),
         ))
         .id();
 
-    for (i, upgrade) in upgrades.iter().enumerate() {
+    for (i, (content, option, synergies)) in cards.into_iter().enumerate() {
+        let curse = option.curse.clone();
+        let background = if synergies.is_empty() {
+            ui::palette::GRAY
+        } else {
+            ui::palette::DARK_GREEN
+        };
         let option = world
             .spawn((
                 Node {
@@ -146,14 +248,14 @@ fn spawn_level_transition_menu(world: &mut World) {
                     flex_direction: FlexDirection::Column,
                     ..Default::default()
                 },
-                BackgroundColor(ui::palette::GRAY),
+                BackgroundColor(background),
                 BorderColor(ui::palette::WHITE),
                 MultiChoiceButton {
                     on_selected: Some(pressed_system_id),
                     activate: activate_id,
                     deactivate: deactivate_id,
                 },
-                UpgradeOption(upgrade.as_ref().map(|(upgrade, _)| (*upgrade).clone())),
+                option,
                 Name::new(format!("Upgrade {i}")),
             ))
             .id();
@@ -189,11 +291,10 @@ fn spawn_level_transition_menu(world: &mut World) {
         let title = world
             .spawn((
                 Text(
-                    match upgrade {
-                        None => "Placeholder",
-                        Some((upgrade, _)) => upgrade.upgrade.name,
-                    }
-                    .into(),
+                    content
+                        .as_ref()
+                        .map_or("Placeholder", |content| content.name)
+                        .into(),
                 ),
                 TextLayout::new_with_justify(JustifyText::Center),
                 TextFont {
@@ -221,11 +322,7 @@ fn spawn_level_transition_menu(world: &mut World) {
             })
             .id();
 
-        let text = if let Some((upgrade, _)) = upgrade {
-            upgrade.upgrade.description
-        } else {
-            ""
-        };
+        let text = content.as_ref().map_or("", |content| content.description);
         // BUG: I think that if I have a div (flexbox, idk if related), inside of which there is a text node, and the text-node is multiline, it will ignore right-padding
         // Wrapping the text in description_div fixed it (TODO: open an issue)
         let description = world.spawn((
@@ -238,6 +335,43 @@ fn spawn_level_transition_menu(world: &mut World) {
             Name::new("Description"),
         ));
         let description = description.id();
+        world.entity_mut(description_div).add_child(description);
+
+        // The drawback is shown on the same card, since picking the upgrade means picking it too
+        if let Some(curse) = curse {
+            let curse_text = world
+                .spawn((
+                    Text(format!(
+                        "Curse: {}\n{}",
+                        curse.upgrade.name, curse.upgrade.description
+                    )),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(ui::palette::RED),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    Name::new("Curse"),
+                ))
+                .id();
+            world.entity_mut(description_div).add_child(curse_text);
+        }
+
+        if !synergies.is_empty() {
+            let synergy_text = world
+                .spawn((
+                    Text(format!("Completes: {}", synergies.join(", "))),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(ui::palette::GREEN),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    Name::new("Synergies"),
+                ))
+                .id();
+            world.entity_mut(description_div).add_child(synergy_text);
+        }
 
         let mut icon = world.spawn((
             Node {
@@ -251,8 +385,8 @@ fn spawn_level_transition_menu(world: &mut World) {
             Name::new("Image"),
         ));
 
-        if let Some((_, icon_path)) = upgrade {
-            icon.insert(ImageNode::new((*icon_path).clone()));
+        if let Some(content) = content {
+            icon.insert(ImageNode::new(content.icon));
             icon.get_mut::<BackgroundColor>().unwrap().0 = ui::palette::WHITE;
         }
         let icon = icon.id();
@@ -261,7 +395,6 @@ fn spawn_level_transition_menu(world: &mut World) {
         world.entity_mut(header).add_child(title);
         world.entity_mut(option).add_child(body);
         world.entity_mut(body).add_child(description_div);
-        world.entity_mut(description_div).add_child(description);
         world.entity_mut(body).add_child(icon);
 
         world.entity_mut(menu_body).add_child(option);
@@ -308,10 +441,27 @@ fn process_upgrade_and_go_to_next_level(
     mut commands: Commands,
     mut finished_event: EventWriter<FinishedLevelTransitionEvent>,
     upgrade_applier: Res<UpgradeApplier>,
+    mut inventory: ResMut<Inventory>,
+    mut heal_writer: EventWriter<HealEvent>,
+    q_player: Query<(Entity, &Health), With<Player>>,
     q_upgrade: Query<&UpgradeOption>,
 ) {
-    if let Some(upgrade) = q_upgrade.get(menu_item_entity).unwrap().0.clone() {
-        commands.run_system_with(upgrade_applier.apply_upgrade_to_all, upgrade);
+    let option = q_upgrade.get(menu_item_entity).unwrap();
+    for upgrade in [&option.upgrade, &option.curse].into_iter().flatten() {
+        commands.run_system_with(upgrade_applier.apply_upgrade_to_all, upgrade.clone());
+    }
+    if let Some(item) = option.item {
+        if !inventory.add(item) {
+            info!("No room for the {}", item.name());
+        }
+    }
+    if let Some(fraction) = option.heal {
+        for (player, health) in q_player.iter() {
+            heal_writer.write(HealEvent {
+                target_entity: player,
+                amount: health.max_health * fraction,
+            });
+        }
     }
     finished_event.write(FinishedLevelTransitionEvent);
 }
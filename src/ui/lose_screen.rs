@@ -1,8 +1,8 @@
 use bevy::prelude::*;
 
-use crate::{utils::z_index, AppState};
+use crate::AppState;
 
-use super::palette;
+use super::run_end_menu::{spawn_run_end_popup, RunEndMenuSystems};
 
 #[derive(Event)]
 pub struct LoseEvent;
@@ -26,32 +26,6 @@ fn transition_to_defeat(
     }
 }
 
-fn spawn_menu_ui(mut commands: Commands) {
-    commands
-        .spawn((
-            Node {
-                width: Val::Percent(40.0),
-                height: Val::Percent(40.0),
-                left: Val::Percent(30.),
-                bottom: Val::Percent(30.),
-                border: UiRect::all(Val::Px(5.)),
-                justify_content: JustifyContent::SpaceAround,
-                position_type: PositionType::Absolute,
-                display: Display::Flex,
-                ..default()
-            },
-            BackgroundColor(palette::DARK_GRAY),
-            BorderColor(Color::BLACK),
-            z_index::POPUP_MENU,
-            Name::new("Lose Screen"),
-        ))
-        .with_children(|commands| {
-            commands.spawn((
-                Text("You Lose!".into()),
-                TextFont {
-                    font_size: 32.0,
-                    ..default()
-                },
-            ));
-        });
+fn spawn_menu_ui(mut commands: Commands, systems: Res<RunEndMenuSystems>) {
+    spawn_run_end_popup(&mut commands, &systems, "You Lose!", "Lose Screen");
 }
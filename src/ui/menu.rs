@@ -1,41 +1,95 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemId, prelude::*};
 
 use crate::{
-    metagame::StartGameEvent,
+    metagame::{save_exists, ChooseMapNodeEvent, ContinueRunEvent, MetagameProgression},
     utils::{
         data_structures::Index,
-        menu_system::{MenuStack, MultiChoiceButton, MultiChoiceParent},
+        menu_system::{MenuStack, MultiChoiceButton, MultiChoiceParent, SpawnedMenu},
         z_index,
     },
     AppState,
 };
 
-use super::palette;
+use super::{item_menu::OpenItemMenuEvent, palette};
 
 pub struct MenuUI;
 
 impl Plugin for MenuUI {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            OnEnter(AppState::ActionMenu),
-            (spawn_menu_ui, show_menu).chain(),
-        )
-        .add_systems(OnExit(AppState::ActionMenu), hide_menu);
+        app.init_resource::<MenuSystems>()
+            .add_systems(
+                OnEnter(AppState::ActionMenu),
+                (spawn_menu_ui, show_menu).chain(),
+            )
+            .add_systems(OnExit(AppState::ActionMenu), despawn_menu)
+            .add_systems(
+                Update,
+                (despawn_menu, spawn_menu_ui, show_menu).chain().run_if(
+                    in_state(AppState::ActionMenu).and(resource_changed::<MetagameProgression>),
+                ),
+            );
     }
 }
 
-#[derive(Clone, Copy, Event, PartialEq, Eq, strum_macros::EnumIter, strum_macros::Display)]
-enum Buttons {
-    Start,
+/// The buttons' systems, registered once and shared by every rebuild of the menu.
+#[derive(Resource)]
+struct MenuSystems {
+    activate: SystemId<In<Entity>>,
+    deactivate: SystemId<In<Entity>>,
+    choose_node: SystemId<In<Entity>>,
+    continue_run: SystemId<In<Entity>>,
+    open_items: SystemId<In<Entity>>,
+}
+
+impl FromWorld for MenuSystems {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            activate: world.register_system(activate),
+            deactivate: world.register_system(deactivate),
+            choose_node: world.register_system(choose_node),
+            continue_run: world.register_system(continue_run),
+            open_items: world.register_system(open_items),
+        }
+    }
 }
 
-const MENU_ITEMS: [Buttons; 1] = [Buttons::Start];
+#[derive(Component)]
+struct ActionMenu;
+
+/// The map node this button picks.
+#[derive(Component)]
+struct MapChoice(usize);
 
-fn spawn_menu_ui(world: &mut World) {
-    let activate_id = world.register_system(activate);
-    let deactivate_id = world.register_system(deactivate);
-    let attack_pressed_id = world.register_system(start_game);
-    world
+fn spawn_menu_ui(
+    mut commands: Commands,
+    systems: Res<MenuSystems>,
+    progression: Res<MetagameProgression>,
+) {
+    let layer = progression.path.len();
+    let Some(nodes) = progression.map.layers.get(layer) else {
+        return;
+    };
+    let choices = progression.available_nodes();
+    // Offered until the player starts a new run instead
+    let can_continue = progression.path.is_empty() && save_exists();
+    let button_node = Node {
+        border: UiRect::all(Val::Px(5.)),
+        width: Val::Percent(20.),
+        height: Val::Auto,
+        align_items: AlignItems::Start,
+        justify_content: JustifyContent::Center,
+        ..default()
+    };
+    let button_text = |text: &str| {
+        (
+            Text(text.into()),
+            TextFont {
+                font_size: 32.0,
+                ..default()
+            },
+        )
+    };
+    commands
         .spawn((
             Node {
                 width: Val::Percent(100.0),
@@ -52,49 +106,50 @@ fn spawn_menu_ui(world: &mut World) {
             BackgroundColor(palette::DARK_GRAY),
             Visibility::Hidden,
             MultiChoiceParent {
-                selected: Index::new(MENU_ITEMS.len(), 0),
+                selected: Index::new(choices.len() + can_continue as usize + 1, 0),
             },
+            ActionMenu,
             Name::new("UI Root"),
         ))
         .with_children(|commands| {
-            for button in MENU_ITEMS.iter() {
+            if can_continue {
                 commands
                     .spawn((
-                        Node {
-                            border: UiRect::all(Val::Px(5.)),
-                            width: Val::Percent(20.),
-                            height: Val::Auto,
-                            align_items: AlignItems::Start,
-                            justify_content: JustifyContent::Center,
-                            ..default()
+                        button_node.clone(),
+                        BorderColor(palette::BLACK),
+                        MultiChoiceButton {
+                            on_selected: Some(systems.continue_run),
+                            activate: systems.activate,
+                            deactivate: systems.deactivate,
                         },
-                        BorderColor(if *button == Buttons::Start {
-                            palette::GREEN
-                        } else {
-                            palette::BLACK
-                        }),
+                    ))
+                    .with_child(button_text("Continue"));
+            }
+            for node in choices {
+                commands
+                    .spawn((
+                        button_node.clone(),
+                        BorderColor(palette::BLACK),
                         MultiChoiceButton {
-                            on_selected: {
-                                if *button == Buttons::Start {
-                                    Some(attack_pressed_id)
-                                } else {
-                                    None
-                                }
-                            },
-                            activate: activate_id,
-                            deactivate: deactivate_id,
+                            on_selected: Some(systems.choose_node),
+                            activate: systems.activate,
+                            deactivate: systems.deactivate,
                         },
+                        MapChoice(node),
                     ))
-                    .with_children(|commands| {
-                        commands.spawn((
-                            Text(button.to_string()),
-                            TextFont {
-                                font_size: 32.0,
-                                ..default()
-                            },
-                        ));
-                    });
+                    .with_child(button_text(nodes[node].step.label()));
             }
+            commands
+                .spawn((
+                    button_node.clone(),
+                    BorderColor(palette::BLACK),
+                    MultiChoiceButton {
+                        on_selected: Some(systems.open_items),
+                        activate: systems.activate,
+                        deactivate: systems.deactivate,
+                    },
+                ))
+                .with_child(button_text("Item"));
         });
 }
 
@@ -106,28 +161,47 @@ fn activate(In(entity): In<Entity>, mut border_query: Query<&mut BorderColor>) {
     border_query.get_mut(entity).unwrap().0 = palette::GREEN;
 }
 
-fn start_game(In(_entity): In<Entity>, mut start_game_event: EventWriter<StartGameEvent>) {
-    start_game_event.write(StartGameEvent);
+fn choose_node(
+    In(entity): In<Entity>,
+    q_choices: Query<&MapChoice>,
+    mut choose_event: EventWriter<ChooseMapNodeEvent>,
+) {
+    if let Ok(MapChoice(node)) = q_choices.get(entity) {
+        choose_event.write(ChooseMapNodeEvent { node: *node });
+    }
 }
 
-fn hide_menu(
-    mut menu_query: Query<(Entity, &mut Visibility), With<MultiChoiceParent>>,
-    mut menu_stack: ResMut<MenuStack>,
-) -> Result {
-    let (entity, mut menu_visibility) = menu_query.single_mut()?;
-    *menu_visibility = Visibility::Hidden;
-    menu_stack.pop_menu(entity);
+fn continue_run(In(_entity): In<Entity>, mut continue_event: EventWriter<ContinueRunEvent>) {
+    continue_event.write(ContinueRunEvent);
+}
 
-    Ok(())
+fn open_items(In(_entity): In<Entity>, mut open_event: EventWriter<OpenItemMenuEvent>) {
+    open_event.write(OpenItemMenuEvent);
+}
+
+fn despawn_menu(
+    menu_query: Query<Entity, With<ActionMenu>>,
+    mut menu_stack: ResMut<MenuStack>,
+    mut commands: Commands,
+) {
+    for entity in menu_query.iter() {
+        if menu_stack.get_current_menu() == Some(entity) {
+            menu_stack.pop_menu(entity);
+        }
+        commands.entity(entity).despawn();
+    }
 }
 
 fn show_menu(
-    mut menu_query: Query<(Entity, &mut Visibility), With<MultiChoiceParent>>,
+    mut menu_query: Query<(Entity, &mut Visibility), With<ActionMenu>>,
     mut menu_stack: ResMut<MenuStack>,
-) -> Result {
-    let (entity, mut menu_visibility) = menu_query.single_mut()?;
+    mut spawned_menu: EventWriter<SpawnedMenu>,
+) {
+    // There's nothing left to choose once the run is over
+    let Ok((entity, mut menu_visibility)) = menu_query.single_mut() else {
+        return;
+    };
     *menu_visibility = Visibility::Visible;
     menu_stack.push_menu(entity);
-
-    Ok(())
+    spawned_menu.write(SpawnedMenu(entity));
 }
@@ -4,7 +4,11 @@
 
 pub mod level_transition;
 
+pub mod item_menu;
 pub mod lose_screen;
 pub mod menu;
 pub mod palette;
+pub mod pause_menu;
+pub mod run_end_menu;
+pub mod synergy_popup;
 pub mod victory_screen;
@@ -6,4 +6,7 @@ pub const DARK_GREEN: Color = Color::Srgba(Srgba::rgb(0.0, 0.5, 0.0));
 pub const WHITE: Color = Color::Srgba(bevy::color::palettes::css::WHITE);
 pub const BLACK: Color = Color::Srgba(bevy::color::palettes::css::BLACK);
 pub const GREEN: Color = Color::Srgba(bevy::color::palettes::css::LIME);
+pub const RED: Color = Color::Srgba(bevy::color::palettes::css::RED);
+pub const BLUE: Color = Color::Srgba(bevy::color::palettes::css::DEEP_SKY_BLUE);
+pub const GOLD: Color = Color::Srgba(bevy::color::palettes::css::GOLD);
 pub const GRAY: Color = Color::Srgba(bevy::color::palettes::css::GRAY);
@@ -0,0 +1,335 @@
+use bevy::{
+    ecs::system::SystemId,
+    prelude::*,
+    window::{PrimaryWindow, WindowMode},
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    metagame::RestartLevelEvent,
+    utils::{
+        data_structures::Index,
+        menu_system::{MenuStack, MultiChoiceButton, MultiChoiceParent, SpawnedMenu},
+        resources::GameFreeze,
+        z_index,
+    },
+    AppState, DefendingState,
+};
+
+use super::palette;
+
+/// Escape pauses the fight, freezing it behind a menu until it's resumed.
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenuSystems>()
+            .add_systems(Update, toggle_pause.run_if(in_state(AppState::Defending)))
+            .add_systems(
+                OnEnter(DefendingState::Paused),
+                (pause_game, spawn_pause_menu),
+            )
+            .add_systems(
+                OnExit(DefendingState::Paused),
+                (close_pause_menu, unpause_game),
+            );
+    }
+}
+
+#[derive(Clone, Copy, strum_macros::EnumIter, strum_macros::Display)]
+enum PauseButton {
+    Resume,
+    Settings,
+    #[strum(to_string = "Restart Level")]
+    RestartLevel,
+    Quit,
+}
+
+#[derive(Resource)]
+struct PauseMenuSystems {
+    activate: SystemId<In<Entity>>,
+    deactivate: SystemId<In<Entity>>,
+    resume: SystemId<In<Entity>>,
+    open_settings: SystemId<In<Entity>>,
+    restart_level: SystemId<In<Entity>>,
+    quit: SystemId<In<Entity>>,
+    toggle_fullscreen: SystemId<In<Entity>>,
+    close_settings: SystemId<In<Entity>>,
+}
+
+impl FromWorld for PauseMenuSystems {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            activate: world.register_system(activate),
+            deactivate: world.register_system(deactivate),
+            resume: world.register_system(resume),
+            open_settings: world.register_system(open_settings),
+            restart_level: world.register_system(restart_level),
+            quit: world.register_system(quit),
+            toggle_fullscreen: world.register_system(toggle_fullscreen),
+            close_settings: world.register_system(close_settings),
+        }
+    }
+}
+
+impl PauseMenuSystems {
+    fn on_selected(&self, button: PauseButton) -> SystemId<In<Entity>> {
+        match button {
+            PauseButton::Resume => self.resume,
+            PauseButton::Settings => self.open_settings,
+            PauseButton::RestartLevel => self.restart_level,
+            PauseButton::Quit => self.quit,
+        }
+    }
+}
+
+/// A popup of the pause menu, which gets despawned when the game is resumed.
+#[derive(Component)]
+struct PausePopup;
+
+/// The direct parent of the pause menu's buttons.
+#[derive(Component)]
+struct PauseMenu;
+
+/// The direct parent of the settings' buttons, shown over the pause menu.
+#[derive(Component)]
+struct SettingsMenu;
+
+fn toggle_pause(
+    input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<DefendingState>>,
+    mut next_state: ResMut<NextState<DefendingState>>,
+) {
+    if !input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    match state.get() {
+        DefendingState::Fighting => next_state.set(DefendingState::Paused),
+        DefendingState::Paused => next_state.set(DefendingState::Fighting),
+        // The death sequence plays out on its own
+        DefendingState::Dying => {}
+    }
+}
+
+const PAUSE_FREEZE: &str = "pause_menu";
+
+fn pause_game(mut commands: Commands) {
+    GameFreeze::add(&mut commands, PAUSE_FREEZE);
+}
+
+/// Whatever else froze the game (like a boss' phase transition) keeps it frozen.
+fn unpause_game(mut commands: Commands) {
+    GameFreeze::remove(&mut commands, PAUSE_FREEZE);
+}
+
+fn menu_button(
+    text: String,
+    on_selected: SystemId<In<Entity>>,
+    systems: &PauseMenuSystems,
+) -> impl Bundle {
+    (
+        Node {
+            border: UiRect::all(Val::Px(5.)),
+            padding: UiRect::horizontal(Val::Px(10.)),
+            ..default()
+        },
+        BorderColor(palette::BLACK),
+        MultiChoiceButton {
+            on_selected: Some(on_selected),
+            activate: systems.activate,
+            deactivate: systems.deactivate,
+        },
+        children![(
+            Text(text),
+            TextFont {
+                font_size: 24.0,
+                ..default()
+            },
+        )],
+    )
+}
+
+/// Spawns a popup with `title` above `buttons`, and makes it the current menu.
+fn spawn_popup(
+    commands: &mut Commands,
+    menu_stack: &mut MenuStack,
+    spawned_menu: &mut EventWriter<SpawnedMenu>,
+    title: &str,
+    menu: impl Bundle,
+    buttons: Vec<impl Bundle>,
+) {
+    let button_count = buttons.len();
+    let menu = commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::SpaceAround,
+                ..default()
+            },
+            MultiChoiceParent {
+                selected: Index::new(button_count, 0),
+            },
+            menu,
+        ))
+        .with_children(|builder| {
+            for button in buttons {
+                builder.spawn(button);
+            }
+        })
+        .id();
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(60.0),
+                height: Val::Percent(40.0),
+                left: Val::Percent(20.),
+                bottom: Val::Percent(30.),
+                border: UiRect::all(Val::Px(5.)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceAround,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            BackgroundColor(palette::DARK_GRAY),
+            BorderColor(Color::BLACK),
+            z_index::POPUP_MENU,
+            PausePopup,
+            Name::new(title.to_string()),
+            children![(
+                Text(title.into()),
+                TextFont {
+                    font_size: 32.0,
+                    ..default()
+                },
+            )],
+        ))
+        .add_child(menu);
+    menu_stack.push_menu(menu);
+    spawned_menu.write(SpawnedMenu(menu));
+}
+
+fn spawn_pause_menu(
+    systems: Res<PauseMenuSystems>,
+    mut menu_stack: ResMut<MenuStack>,
+    mut spawned_menu: EventWriter<SpawnedMenu>,
+    mut commands: Commands,
+) {
+    let buttons: Vec<_> = PauseButton::iter()
+        .map(|button| menu_button(button.to_string(), systems.on_selected(button), &systems))
+        .collect();
+    spawn_popup(
+        &mut commands,
+        &mut menu_stack,
+        &mut spawned_menu,
+        "Paused",
+        (PauseMenu, Name::new("Pause Menu")),
+        buttons,
+    );
+}
+
+fn close_pause_menu(
+    q_settings: Query<Entity, With<SettingsMenu>>,
+    q_menu: Query<Entity, With<PauseMenu>>,
+    q_popups: Query<Entity, With<PausePopup>>,
+    mut menu_stack: ResMut<MenuStack>,
+    mut commands: Commands,
+) {
+    // The settings are above the pause menu in the stack
+    for menu in q_settings.iter().chain(q_menu.iter()) {
+        if menu_stack.get_current_menu() == Some(menu) {
+            menu_stack.pop_menu(menu);
+        }
+    }
+    for popup in q_popups.iter() {
+        commands.entity(popup).despawn();
+    }
+}
+
+fn deactivate(In(entity): In<Entity>, mut border_query: Query<&mut BorderColor>) {
+    border_query.get_mut(entity).unwrap().0 = palette::BLACK;
+}
+
+fn activate(In(entity): In<Entity>, mut border_query: Query<&mut BorderColor>) {
+    border_query.get_mut(entity).unwrap().0 = palette::GREEN;
+}
+
+fn resume(In(_entity): In<Entity>, mut next_state: ResMut<NextState<DefendingState>>) {
+    next_state.set(DefendingState::Fighting);
+}
+
+fn restart_level(In(_entity): In<Entity>, mut restart_writer: EventWriter<RestartLevelEvent>) {
+    restart_writer.write(RestartLevelEvent);
+}
+
+/// The run was saved when the map was last visited, so it can be continued from there.
+fn quit(In(_entity): In<Entity>, mut app_exit: EventWriter<AppExit>) {
+    app_exit.write(AppExit::Success);
+}
+
+fn fullscreen_text(window: &Window) -> String {
+    let fullscreen = !matches!(window.mode, WindowMode::Windowed);
+    format!("Fullscreen: {}", if fullscreen { "On" } else { "Off" })
+}
+
+fn open_settings(
+    In(_entity): In<Entity>,
+    q_settings: Query<(), With<SettingsMenu>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    systems: Res<PauseMenuSystems>,
+    mut menu_stack: ResMut<MenuStack>,
+    mut spawned_menu: EventWriter<SpawnedMenu>,
+    mut commands: Commands,
+) {
+    if !q_settings.is_empty() {
+        return;
+    }
+    let buttons = vec![
+        menu_button(
+            fullscreen_text(&window),
+            systems.toggle_fullscreen,
+            &systems,
+        ),
+        menu_button("Back".into(), systems.close_settings, &systems),
+    ];
+    spawn_popup(
+        &mut commands,
+        &mut menu_stack,
+        &mut spawned_menu,
+        "Settings",
+        (SettingsMenu, Name::new("Settings Menu")),
+        buttons,
+    );
+}
+
+fn close_settings(
+    In(_entity): In<Entity>,
+    q_settings: Query<(Entity, &ChildOf), With<SettingsMenu>>,
+    mut menu_stack: ResMut<MenuStack>,
+    mut commands: Commands,
+) {
+    for (menu, child_of) in q_settings.iter() {
+        menu_stack.pop_menu(menu);
+        commands.entity(child_of.parent()).despawn();
+    }
+}
+
+fn toggle_fullscreen(
+    In(entity): In<Entity>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+    q_children: Query<&Children>,
+    mut q_text: Query<&mut Text>,
+) {
+    window.mode = match window.mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen(MonitorSelection::Primary),
+        _ => WindowMode::Windowed,
+    };
+    let Ok(children) = q_children.get(entity) else {
+        return;
+    };
+    for child in children.iter() {
+        if let Ok(mut text) = q_text.get_mut(child) {
+            text.0 = fullscreen_text(&window);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use bevy::{ecs::system::SystemId, prelude::*};
+use strum::IntoEnumIterator;
+
+use crate::{
+    utils::{
+        data_structures::Index,
+        menu_system::{MenuStack, MultiChoiceButton, MultiChoiceParent, SpawnedMenu},
+        resources::RunRng,
+        z_index,
+    },
+    AppState, ResetRunEvent, RewindRunEvent,
+};
+
+use super::palette;
+
+/// The popup shared by the defeat and victory screens, offering to start over.
+pub struct RunEndMenuPlugin;
+
+impl Plugin for RunEndMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunEndMenuSystems>()
+            .add_systems(Update, register_menu)
+            .add_systems(OnExit(AppState::Defeat), despawn_run_end_popup)
+            .add_systems(OnExit(AppState::Victory), despawn_run_end_popup);
+    }
+}
+
+#[derive(Clone, Copy, strum_macros::EnumIter, strum_macros::Display)]
+enum RunEndButton {
+    /// Plays the same run again, from the same seed.
+    Retry,
+    #[strum(to_string = "New Run")]
+    NewRun,
+    Quit,
+}
+
+#[derive(Resource)]
+pub struct RunEndMenuSystems {
+    activate: SystemId<In<Entity>>,
+    deactivate: SystemId<In<Entity>>,
+    retry: SystemId<In<Entity>>,
+    new_run: SystemId<In<Entity>>,
+    quit: SystemId<In<Entity>>,
+}
+
+impl FromWorld for RunEndMenuSystems {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            activate: world.register_system(activate),
+            deactivate: world.register_system(deactivate),
+            retry: world.register_system(retry),
+            new_run: world.register_system(new_run),
+            quit: world.register_system(quit),
+        }
+    }
+}
+
+impl RunEndMenuSystems {
+    fn on_selected(&self, button: RunEndButton) -> SystemId<In<Entity>> {
+        match button {
+            RunEndButton::Retry => self.retry,
+            RunEndButton::NewRun => self.new_run,
+            RunEndButton::Quit => self.quit,
+        }
+    }
+}
+
+/// The root of the popup, which gets despawned when leaving the screen.
+#[derive(Component)]
+pub struct RunEndPopup;
+
+/// The direct parent of the buttons, for registering the menu.
+#[derive(Component)]
+struct RunEndMenu;
+
+/// Spawns a popup with `title` above the Retry/New Run/Quit buttons, and returns the popup.
+pub fn spawn_run_end_popup(
+    commands: &mut Commands,
+    systems: &RunEndMenuSystems,
+    title: &str,
+    name: &'static str,
+) -> Entity {
+    let buttons = commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::SpaceAround,
+                ..default()
+            },
+            MultiChoiceParent {
+                selected: Index::new(RunEndButton::iter().count(), 0),
+            },
+            RunEndMenu,
+            Name::new("Run End Menu"),
+        ))
+        .with_children(|commands| {
+            for button in RunEndButton::iter() {
+                commands.spawn((
+                    Node {
+                        border: UiRect::all(Val::Px(5.)),
+                        padding: UiRect::horizontal(Val::Px(10.)),
+                        ..default()
+                    },
+                    BorderColor(palette::BLACK),
+                    MultiChoiceButton {
+                        on_selected: Some(systems.on_selected(button)),
+                        activate: systems.activate,
+                        deactivate: systems.deactivate,
+                    },
+                    children![(
+                        Text(button.to_string()),
+                        TextFont {
+                            font_size: 24.0,
+                            ..default()
+                        },
+                    )],
+                ));
+            }
+        })
+        .id();
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(60.0),
+                height: Val::Percent(70.0),
+                left: Val::Percent(20.),
+                bottom: Val::Percent(15.),
+                border: UiRect::all(Val::Px(5.)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceAround,
+                position_type: PositionType::Absolute,
+                display: Display::Flex,
+                ..default()
+            },
+            BackgroundColor(palette::DARK_GRAY),
+            BorderColor(Color::BLACK),
+            z_index::POPUP_MENU,
+            RunEndPopup,
+            Name::new(name),
+            children![(
+                Text(title.into()),
+                TextFont {
+                    font_size: 32.0,
+                    ..default()
+                },
+            )],
+        ))
+        .add_child(buttons)
+        .id()
+}
+
+fn register_menu(
+    menu_query: Query<Entity, Added<RunEndMenu>>,
+    mut menu_stack: ResMut<MenuStack>,
+    mut writer: EventWriter<SpawnedMenu>,
+) {
+    for menu in menu_query.iter() {
+        menu_stack.push_menu(menu);
+        writer.write(SpawnedMenu(menu));
+    }
+}
+
+fn despawn_run_end_popup(
+    q_popup: Query<Entity, With<RunEndPopup>>,
+    q_menu: Query<Entity, With<RunEndMenu>>,
+    mut menu_stack: ResMut<MenuStack>,
+    mut commands: Commands,
+) {
+    for menu in q_menu.iter() {
+        if menu_stack.get_current_menu() == Some(menu) {
+            menu_stack.pop_menu(menu);
+        }
+    }
+    for popup in q_popup.iter() {
+        commands.entity(popup).despawn();
+    }
+}
+
+fn deactivate(In(entity): In<Entity>, mut border_query: Query<&mut BorderColor>) {
+    border_query.get_mut(entity).unwrap().0 = palette::BLACK;
+}
+
+fn activate(In(entity): In<Entity>, mut border_query: Query<&mut BorderColor>) {
+    border_query.get_mut(entity).unwrap().0 = palette::GREEN;
+}
+
+fn retry(In(_entity): In<Entity>, mut run_rng: ResMut<RunRng>, mut commands: Commands) {
+    *run_rng = RunRng::new(run_rng.seed());
+    commands.trigger(ResetRunEvent);
+    commands.trigger(RewindRunEvent);
+}
+
+fn new_run(In(_entity): In<Entity>, mut run_rng: ResMut<RunRng>, mut commands: Commands) {
+    *run_rng = RunRng::default();
+    commands.trigger(ResetRunEvent);
+    commands.trigger(RewindRunEvent);
+}
+
+fn quit(In(_entity): In<Entity>, mut app_exit: EventWriter<AppExit>) {
+    app_exit.write(AppExit::Success);
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+
+use crate::{upgrades::SynergyActivatedEvent, utils::z_index};
+
+use super::palette;
+
+const POPUP_DURATION: f32 = 3.;
+
+/// Announces the synergies the player completes.
+pub struct SynergyPopupPlugin;
+
+impl Plugin for SynergyPopupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (spawn_synergy_popup, despawn_old_popups));
+    }
+}
+
+#[derive(Component)]
+struct SynergyPopup {
+    timer: Timer,
+}
+
+fn spawn_synergy_popup(mut events: EventReader<SynergyActivatedEvent>, mut commands: Commands) {
+    for event in events.read() {
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(5.),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            z_index::NOTIFICATION,
+            SynergyPopup {
+                timer: Timer::from_seconds(POPUP_DURATION, TimerMode::Once),
+            },
+            Name::new("Synergy Popup"),
+            children![(
+                Text(format!("Synergy: {}!", event.name)),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(palette::GREEN),
+            )],
+        ));
+    }
+}
+
+fn despawn_old_popups(
+    mut q_popups: Query<(Entity, &mut SynergyPopup)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut popup) in q_popups.iter_mut() {
+        popup.timer.tick(time.delta());
+        if popup.timer.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
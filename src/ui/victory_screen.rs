@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 
-use crate::{ui, utils::z_index, AppState};
+use crate::AppState;
+
+use super::run_end_menu::{spawn_run_end_popup, RunEndMenuSystems};
 
 pub struct VictoryScreenPlugin;
 
@@ -10,32 +12,6 @@ impl Plugin for VictoryScreenPlugin {
     }
 }
 
-fn spawn_victory_popup(mut commands: Commands) {
-    commands
-        .spawn((
-            Node {
-                width: Val::Percent(40.0),
-                height: Val::Percent(40.0),
-                left: Val::Percent(30.),
-                bottom: Val::Percent(30.),
-                border: UiRect::all(Val::Px(5.)),
-                justify_content: JustifyContent::SpaceAround,
-                position_type: PositionType::Absolute,
-                display: Display::Flex,
-                ..default()
-            },
-            BackgroundColor(ui::palette::DARK_GRAY),
-            BorderColor(Color::BLACK),
-            z_index::POPUP_MENU,
-            Name::new("Victory Screen"),
-        ))
-        .with_children(|commands| {
-            commands.spawn((
-                Text("You Win!".into()),
-                TextFont {
-                    font_size: 32.0,
-                    ..default()
-                },
-            ));
-        });
+fn spawn_victory_popup(mut commands: Commands, systems: Res<RunEndMenuSystems>) {
+    spawn_run_end_popup(&mut commands, &systems, "You Win!", "Victory Screen");
 }
@@ -0,0 +1,53 @@
+use bevy::{ecs::system::SystemId, prelude::*};
+
+/// The gameplay events an upgrade can react to. Each one concerns a specific entity - the one that
+/// got damaged, dashed, parried etc. - and only that entity's hooks fire.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HookTrigger {
+    Damaged,
+    Dash,
+    SwordHit,
+    Parry,
+    /// Fires for every entity with hooks.
+    LevelStarted,
+    /// Fires for every entity with hooks.
+    LevelFinished,
+    /// A bullet passed close to the entity without hitting it.
+    Graze,
+}
+
+#[derive(Clone, Copy)]
+pub struct UpgradeHook {
+    /// The upgrade that added the hook, which removes it when it goes away.
+    pub upgrade_id: &'static str,
+    pub trigger: HookTrigger,
+    /// Gets the entity that owns the hook.
+    pub effect: SystemId<In<Entity>>,
+}
+
+/// Reactions that upgrades added to this entity.
+#[derive(Component, Default)]
+pub struct UpgradeHooks {
+    hooks: Vec<UpgradeHook>,
+}
+
+impl UpgradeHooks {
+    /// Meant to be called from an upgrade's `apply_upgrade`, on the entity it got.
+    pub fn add_to(entity: &mut EntityCommands, hook: UpgradeHook) {
+        entity
+            .entry::<UpgradeHooks>()
+            .or_default()
+            .and_modify(move |mut hooks| hooks.hooks.push(hook));
+    }
+
+    pub fn remove_upgrade(&mut self, upgrade_id: &str) {
+        self.hooks.retain(|hook| hook.upgrade_id != upgrade_id);
+    }
+
+    /// Runs the effects of the hooks waiting for `trigger`.
+    pub fn fire(&self, trigger: HookTrigger, owner: Entity, commands: &mut Commands) {
+        for hook in self.hooks.iter().filter(|hook| hook.trigger == trigger) {
+            commands.run_system_with(hook.effect, owner);
+        }
+    }
+}
@@ -1,5 +1,9 @@
+mod hooks;
 mod plugin;
+mod synergy;
 #[cfg(test)]
 mod tests;
 
+pub use hooks::{HookTrigger, UpgradeHook, UpgradeHooks};
 pub use plugin::*;
+pub use synergy::{Synergy, SynergyActivatedEvent, SynergyRegistry};
@@ -1,9 +1,17 @@
 use bevy::{ecs::system::SystemId, prelude::*};
 use bevy_inspector_egui::prelude::*;
 use bitmask_enum::bitmask;
-use std::path::Path;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
 
-use crate::utils::resources::SelectionsPool;
+use crate::{ui::level_transition::ShopType, utils::resources::SelectionsPool, RewindRunEvent};
+
+use super::{
+    hooks::UpgradeHooks,
+    synergy::{self, SynergyActivatedEvent, SynergyRegistry},
+};
 
 pub struct UpgradesPlugin;
 
@@ -11,16 +19,26 @@ impl Plugin for UpgradesPlugin {
     fn build(&self, app: &mut App) {
         let apply_system_id = app.world_mut().register_system(apply_upgrade_to_all);
         app.init_resource::<SelectionsPool<GlobalUpgrade>>()
-            .init_resource::<AppliedGlobalUpgrades>();
+            .init_resource::<AppliedGlobalUpgrades>()
+            .init_resource::<UpgradeRegistry>()
+            .init_resource::<SynergyRegistry>()
+            .add_event::<SynergyActivatedEvent>();
         app.insert_resource(UpgradeApplier {
             apply_upgrade_to_all: apply_system_id,
         })
-        .add_systems(PostUpdate, apply_upgrade_on_spawn); // TODO: Correct schedule to not flicker
+        .add_systems(PostUpdate, apply_upgrade_on_spawn) // TODO: Correct schedule to not flicker
+        .add_systems(
+            Update,
+            synergy::apply_synergies.run_if(resource_changed::<AppliedGlobalUpgrades>),
+        )
+        .add_observer(forget_applied_upgrades);
     }
 }
 
 #[derive(Clone, Copy)]
 pub struct Upgrade {
+    /// A stable name for the upgrade, which unlike `apply_upgrade` can be saved to a file.
+    pub id: &'static str,
     pub apply_upgrade: SystemId<In<Entity>, ()>,
     pub name: &'static str,
     pub description: &'static str,
@@ -97,6 +115,91 @@ pub struct AppliedGlobalUpgrades {
     pub applied_upgrades: Vec<GlobalUpgrade>,
 }
 
+impl AppliedGlobalUpgrades {
+    pub fn contains(&self, id: &str) -> bool {
+        self.applied_upgrades
+            .iter()
+            .any(|upgrade| upgrade.upgrade.id == id)
+    }
+}
+
+/// The upgrades' effects are undone by whoever resets the entities they were applied to,
+/// except for their hooks, which go away together with them.
+fn forget_applied_upgrades(
+    _trigger: Trigger<RewindRunEvent>,
+    mut applied_global_upgrades: ResMut<AppliedGlobalUpgrades>,
+    mut q_hooks: Query<&mut UpgradeHooks>,
+) {
+    for upgrade in applied_global_upgrades.applied_upgrades.drain(..) {
+        for mut hooks in q_hooks.iter_mut() {
+            hooks.remove_upgrade(upgrade.upgrade.id);
+        }
+    }
+}
+
+/// Every upgrade in the game by its id, for turning saved ids back into upgrades.
+/// Upgrades only make it into the shops' pools once they're unlocked.
+#[derive(Resource, Default)]
+pub struct UpgradeRegistry {
+    /// Ordered by id, so that the shops' pools - and the picks for a given seed - come out the same
+    /// on every launch.
+    upgrades: BTreeMap<&'static str, (GlobalUpgrade, Option<ShopType>)>,
+    /// The id of the curse that comes with each upgrade that has one.
+    curses: HashMap<&'static str, &'static str>,
+    /// Upgrades that aren't shown as something the player got, like the difficulty scaling.
+    hidden: HashSet<&'static str>,
+}
+
+impl UpgradeRegistry {
+    /// `shop` is the shop that sells the upgrade.
+    pub fn register(&mut self, upgrade: GlobalUpgrade, shop: ShopType) {
+        self.insert(upgrade, Some(shop));
+    }
+
+    /// For upgrades that the game applies by itself, which no shop sells.
+    pub fn register_unsold(&mut self, upgrade: GlobalUpgrade) {
+        self.insert(upgrade, None);
+    }
+
+    /// Like `register_unsold`, for upgrades the player shouldn't see among the ones they got.
+    pub fn register_hidden(&mut self, upgrade: GlobalUpgrade) {
+        self.hidden.insert(upgrade.upgrade.id);
+        self.insert(upgrade, None);
+    }
+
+    /// A curse is an enemy-side drawback that's applied together with the upgrade `upgrade_id`.
+    pub fn register_curse(&mut self, upgrade_id: &'static str, curse: GlobalUpgrade) {
+        self.curses.insert(upgrade_id, curse.upgrade.id);
+        self.insert(curse, None);
+    }
+
+    fn insert(&mut self, upgrade: GlobalUpgrade, shop: Option<ShopType>) {
+        let previous = self.upgrades.insert(upgrade.upgrade.id, (upgrade, shop));
+        assert!(previous.is_none(), "Two upgrades have the same id");
+    }
+
+    pub fn get(&self, id: &str) -> Option<&GlobalUpgrade> {
+        self.upgrades.get(id).map(|(upgrade, _)| upgrade)
+    }
+
+    pub fn curse_of(&self, upgrade_id: &str) -> Option<&GlobalUpgrade> {
+        self.curses
+            .get(upgrade_id)
+            .and_then(|curse_id| self.get(curse_id))
+    }
+
+    pub fn is_hidden(&self, id: &str) -> bool {
+        self.hidden.contains(id)
+    }
+
+    /// The upgrades that are sold in shops, with the shop that sells them.
+    pub fn iter_sold(&self) -> impl Iterator<Item = (&GlobalUpgrade, ShopType)> {
+        self.upgrades
+            .values()
+            .filter_map(|(upgrade, shop)| shop.map(|shop| (upgrade, shop)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,6 +211,7 @@ mod tests {
         let get_upgrade = |factions| GlobalUpgrade {
             receiver_factions: factions,
             upgrade: Upgrade {
+                id: "",
                 apply_upgrade: empty_system_id,
                 description: "",
                 name: "",
@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+
+use super::{AppliedGlobalUpgrades, GlobalUpgrade, UpgradeApplier};
+
+/// A bonus for owning every upgrade in a combination.
+#[derive(Clone)]
+pub struct Synergy {
+    pub name: &'static str,
+    /// The ids of the upgrades that make up the combination.
+    pub requires: &'static [&'static str],
+    /// Applied (and recorded) like any other upgrade once the combination is complete.
+    pub bonus: GlobalUpgrade,
+}
+
+impl Synergy {
+    fn is_complete(&self, applied: &AppliedGlobalUpgrades) -> bool {
+        self.requires.iter().all(|id| applied.contains(id))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SynergyRegistry {
+    synergies: Vec<Synergy>,
+}
+
+impl SynergyRegistry {
+    pub fn register(&mut self, synergy: Synergy) {
+        self.synergies.push(synergy);
+    }
+
+    /// The synergies that picking `upgrade_id` would complete.
+    pub fn completed_by(
+        &self,
+        upgrade_id: &str,
+        applied: &AppliedGlobalUpgrades,
+    ) -> impl Iterator<Item = &Synergy> {
+        self.synergies.iter().filter(move |synergy| {
+            !synergy.is_complete(applied)
+                && synergy.requires.contains(&upgrade_id)
+                && synergy
+                    .requires
+                    .iter()
+                    .all(|id| *id == upgrade_id || applied.contains(id))
+        })
+    }
+}
+
+#[derive(Event)]
+pub struct SynergyActivatedEvent {
+    pub name: &'static str,
+}
+
+pub(super) fn apply_synergies(
+    registry: Res<SynergyRegistry>,
+    applied: Res<AppliedGlobalUpgrades>,
+    upgrade_applier: Res<UpgradeApplier>,
+    mut activated_writer: EventWriter<SynergyActivatedEvent>,
+    mut commands: Commands,
+) {
+    for synergy in registry.synergies.iter() {
+        // The bonus is recorded, so a loaded run doesn't get it (or the popup) a second time
+        if synergy.is_complete(&applied) && !applied.contains(synergy.bonus.upgrade.id) {
+            commands.run_system_with(upgrade_applier.apply_upgrade_to_all, synergy.bonus.clone());
+            activated_writer.write(SynergyActivatedEvent { name: synergy.name });
+        }
+    }
+}
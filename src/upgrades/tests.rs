@@ -1,11 +1,14 @@
 use std::path::Path;
 
-use bevy::prelude::*;
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
 use bevy_inspector_egui::prelude::*;
 
 use crate::upgrades::UpgradesReceiver;
 
-use super::{GlobalUpgrade, Upgrade, UpgradesReceiverFaction};
+use super::{
+    AppliedGlobalUpgrades, GlobalUpgrade, HookTrigger, Synergy, SynergyRegistry, Upgrade,
+    UpgradeHook, UpgradeHooks, UpgradesReceiverFaction,
+};
 
 #[derive(Component, InspectorOptions, Default, Reflect)]
 #[reflect(Component, InspectorOptions)]
@@ -19,6 +22,7 @@ fn get_custom_upgrade(world: &mut World) -> GlobalUpgrade {
     GlobalUpgrade {
         receiver_factions: UpgradesReceiverFaction::EnemyBullets,
         upgrade: Upgrade {
+            id: "test",
             apply_upgrade: world.register_system(add_custom_component),
             description: "Test",
             name: "Test",
@@ -27,6 +31,12 @@ fn get_custom_upgrade(world: &mut World) -> GlobalUpgrade {
     }
 }
 
+fn get_upgrade_with_id(world: &mut World, id: &'static str) -> GlobalUpgrade {
+    let mut upgrade = get_custom_upgrade(world);
+    upgrade.upgrade.id = id;
+    upgrade
+}
+
 #[test]
 fn test_global_upgrades() {
     use crate::upgrades::{UpgradeApplier, UpgradesPlugin};
@@ -112,3 +122,163 @@ fn test_global_upgrades() {
         assert!(world.entity(entity).get::<HasCustomUpgrade>().is_none());
     }
 }
+
+#[test]
+fn test_synergy_offers() {
+    let mut world = World::new();
+    let mut registry = SynergyRegistry::default();
+    registry.register(Synergy {
+        name: "Both",
+        requires: &["a", "b"],
+        bonus: get_upgrade_with_id(&mut world, "bonus"),
+    });
+    let completes =
+        |applied: &AppliedGlobalUpgrades, id| registry.completed_by(id, applied).count() > 0;
+
+    let mut applied = AppliedGlobalUpgrades::default();
+    assert!(!completes(&applied, "a"));
+    applied
+        .applied_upgrades
+        .push(get_upgrade_with_id(&mut world, "a"));
+    assert!(completes(&applied, "b"));
+    assert!(!completes(&applied, "c"));
+
+    // Owning the whole combination already, there's nothing left to complete
+    applied
+        .applied_upgrades
+        .push(get_upgrade_with_id(&mut world, "b"));
+    assert!(!completes(&applied, "b"));
+}
+
+#[test]
+fn test_synergy_bonus_is_applied_once() {
+    use crate::upgrades::{UpgradeApplier, UpgradesPlugin};
+
+    let mut app = App::new();
+    app.add_plugins(UpgradesPlugin);
+    let world = app.world_mut();
+    let bonus = get_upgrade_with_id(world, "bonus");
+    world.resource_mut::<SynergyRegistry>().register(Synergy {
+        name: "Both",
+        requires: &["a", "b"],
+        bonus,
+    });
+    let apply_upgrade = world.resource::<UpgradeApplier>().apply_upgrade_to_all;
+    let a = get_upgrade_with_id(world, "a");
+    world.run_system_with(apply_upgrade, a).unwrap();
+    app.update();
+    assert!(!app
+        .world()
+        .resource::<AppliedGlobalUpgrades>()
+        .contains("bonus"));
+
+    let world = app.world_mut();
+    let b = get_upgrade_with_id(world, "b");
+    world.run_system_with(apply_upgrade, b).unwrap();
+    app.update();
+    app.update();
+    let world = app.world();
+    let bonuses = world
+        .resource::<AppliedGlobalUpgrades>()
+        .applied_upgrades
+        .iter()
+        .filter(|upgrade| upgrade.upgrade.id == "bonus")
+        .count();
+    assert_eq!(bonuses, 1);
+}
+
+#[derive(Resource, Default)]
+struct HookRuns(u32);
+
+fn count_hook_run(In(_entity): In<Entity>, mut runs: ResMut<HookRuns>) {
+    runs.0 += 1;
+}
+
+#[test]
+fn test_hooks_fire_until_the_upgrade_is_removed() {
+    use crate::{
+        upgrades::{UpgradeApplier, UpgradesPlugin},
+        RewindRunEvent,
+    };
+
+    let mut app = App::new();
+    app.add_plugins(UpgradesPlugin).init_resource::<HookRuns>();
+    let world = app.world_mut();
+    let effect = world.register_system(count_hook_run);
+    let mut upgrade = get_upgrade_with_id(world, "hooked");
+    upgrade.upgrade.apply_upgrade =
+        world.register_system(move |In(entity): In<Entity>, mut commands: Commands| {
+            UpgradeHooks::add_to(
+                &mut commands.entity(entity),
+                UpgradeHook {
+                    upgrade_id: "hooked",
+                    trigger: HookTrigger::Dash,
+                    effect,
+                },
+            );
+        });
+    let owner = world
+        .spawn(UpgradesReceiver {
+            factions: UpgradesReceiverFaction::EnemyBullets,
+        })
+        .id();
+    let apply_upgrade = world.resource::<UpgradeApplier>().apply_upgrade_to_all;
+    world.run_system_with(apply_upgrade, upgrade).unwrap();
+
+    let fire = |world: &mut World, trigger| {
+        world
+            .run_system_once(
+                move |q_hooks: Query<&UpgradeHooks>, mut commands: Commands| {
+                    q_hooks
+                        .get(owner)
+                        .unwrap()
+                        .fire(trigger, owner, &mut commands);
+                },
+            )
+            .unwrap();
+    };
+    fire(world, HookTrigger::Dash);
+    fire(world, HookTrigger::Parry);
+    assert_eq!(world.resource::<HookRuns>().0, 1);
+
+    world.trigger(RewindRunEvent);
+    fire(world, HookTrigger::Dash);
+    assert_eq!(world.resource::<HookRuns>().0, 1);
+}
+
+#[test]
+fn test_level_started_hooks_fire_when_a_fight_starts() {
+    use bevy::state::app::StatesPlugin;
+
+    use crate::{bullet_hell::upgrade_hooks, upgrades::UpgradesPlugin, AppState};
+
+    let mut app = App::new();
+    app.add_plugins((StatesPlugin, UpgradesPlugin))
+        .insert_state(AppState::ActionMenu)
+        .add_plugins(upgrade_hooks::plugin)
+        .init_resource::<HookRuns>();
+    let world = app.world_mut();
+    let effect = world.register_system(count_hook_run);
+    let owner = world.spawn_empty().id();
+    world
+        .run_system_once(move |mut commands: Commands| {
+            UpgradeHooks::add_to(
+                &mut commands.entity(owner),
+                UpgradeHook {
+                    upgrade_id: "hooked",
+                    trigger: HookTrigger::LevelStarted,
+                    effect,
+                },
+            );
+        })
+        .unwrap();
+
+    world.run_schedule(StateTransition);
+    assert_eq!(world.resource::<HookRuns>().0, 0);
+
+    world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Defending);
+    world.run_schedule(StateTransition);
+    assert_eq!(world.resource::<HookRuns>().0, 1);
+}
@@ -119,7 +119,6 @@ impl MenuStack {
 
     pub fn pop_menu(&mut self, new_menu: Entity) {
         assert_eq!(self.menus.pop().unwrap(), new_menu);
-        self.menus.pop();
     }
 }
 
@@ -178,6 +177,10 @@ fn on_menu_spawned(
     mut commands: Commands,
 ) {
     for SpawnedMenu(new_menu) in event.read() {
+        // The menu might have been despawned since (for example if it was rebuilt)
+        if !component_query.q_menu_parent.contains(*new_menu) {
+            continue;
+        }
         let (selected_entity, _) = component_query.get_selected_child(*new_menu);
         for (button_entity, button) in component_query.get_all_children(*new_menu) {
             if button_entity == selected_entity {
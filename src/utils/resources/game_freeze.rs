@@ -0,0 +1,48 @@
+use std::collections::HashSet;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+/// The reasons the game is frozen right now, each added and removed by whoever it belongs to.
+/// The virtual time (and the physics running on it) only resumes once none of them are left.
+#[derive(Resource, Default)]
+pub struct GameFreeze {
+    tags: HashSet<&'static str>,
+}
+
+impl GameFreeze {
+    pub fn add(commands: &mut Commands, tag: &'static str) {
+        commands.queue(move |world: &mut World| {
+            world.get_resource_or_init::<GameFreeze>().tags.insert(tag);
+            set_paused(world, true);
+        });
+    }
+
+    pub fn remove(commands: &mut Commands, tag: &'static str) {
+        commands.queue(move |world: &mut World| {
+            let mut freeze = world.get_resource_or_init::<GameFreeze>();
+            freeze.tags.remove(tag);
+            let still_frozen = !freeze.tags.is_empty();
+            if !still_frozen {
+                set_paused(world, false);
+            }
+        });
+    }
+}
+
+fn set_paused(world: &mut World, paused: bool) {
+    if let Some(mut virtual_time) = world.get_resource_mut::<Time<Virtual>>() {
+        if paused {
+            virtual_time.pause();
+        } else {
+            virtual_time.unpause();
+        }
+    }
+    if let Some(mut physics_time) = world.get_resource_mut::<Time<Physics>>() {
+        if paused {
+            physics_time.pause();
+        } else {
+            physics_time.unpause();
+        }
+    }
+}
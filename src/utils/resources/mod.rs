@@ -1,3 +1,7 @@
+mod game_freeze;
 mod pool;
+mod run_rng;
 
+pub use game_freeze::GameFreeze;
 pub use pool::SelectionsPool;
+pub use run_rng::RunRng;
@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use rand::{seq::IteratorRandom, seq::SliceRandom};
+use rand::{seq::IteratorRandom, seq::SliceRandom, Rng};
 
 /// A resource for managing a pool of selections we can pick from - for example the available upgrades, or the end-of-level effects.
 #[derive(Resource)]
@@ -16,16 +16,16 @@ impl<T: Clone> SelectionsPool<T> {
     //     &self.items[rand::thread_rng().gen_range(0..self.items.len())]
     // }
 
-    pub fn get_multiple_random(&self, count: usize) -> Vec<Option<T>> {
+    pub fn get_multiple_random(&self, count: usize, rng: &mut impl Rng) -> Vec<Option<T>> {
         let mut options: Vec<Option<T>> = self
             .items
             .iter()
-            .choose_multiple(&mut rand::thread_rng(), count)
+            .choose_multiple(rng, count)
             .iter()
             .map(|item| Some((**item).clone()))
             .collect();
         options.append(&mut vec![None; count - options.len()]);
-        options.shuffle(&mut rand::thread_rng());
+        options.shuffle(rng);
         options
     }
 }
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Spreads the steps over the seeds, so that nearby seeds don't share their steps' streams.
+const STEP_MIXER: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// The randomness of the current run - the shops' offers, the events, the enemies that get spawned.
+/// Starting over from the same seed replays the same run.
+/// Every step on the map draws from its own stream, so the seed and the path are all it takes
+/// to pick the run up again from a save.
+#[derive(Resource)]
+pub struct RunRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl RunRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The randomness of the run as it is at the start of its `step`th step.
+    pub fn at_step(seed: u64, step: usize) -> Self {
+        let mut run_rng = Self::new(seed);
+        run_rng.start_step(step);
+        run_rng
+    }
+
+    /// Reseeds from the seed and the step, leaving behind whatever the earlier steps drew.
+    pub fn start_step(&mut self, step: usize) {
+        self.rng = StdRng::seed_from_u64(self.seed ^ (step as u64).wrapping_mul(STEP_MIXER));
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+}
+
+impl Default for RunRng {
+    fn default() -> Self {
+        Self::new(rand::thread_rng().gen())
+    }
+}
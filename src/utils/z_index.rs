@@ -4,3 +4,4 @@ pub const WORLD_UI: ZIndex = ZIndex(0);
 pub const GAME_UI: ZIndex = ZIndex(1);
 pub const START_MENU: ZIndex = ZIndex(2);
 pub const POPUP_MENU: ZIndex = ZIndex(3);
+pub const NOTIFICATION: ZIndex = ZIndex(4);